@@ -0,0 +1,108 @@
+// Row blitting against the per pixel put_pixel loops put_char and draw_letter used to run, for a
+// glyph at the layout's size and at twice that, i.e. on a 4K screen.
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use harlem_shake::blit::{self, Clip};
+use harlem_shake::stroke::THRESHOLD;
+use image::{GrayImage, Luma};
+use std::hint::black_box;
+use x11rb::image::{BitsPerPixel, Image, ImageOrder, ScanlinePad};
+
+const SCALES: &[u32] = &[1, 2];
+// about the size of a capital letter's bitmap at the layout's size
+const WIDTH: u32 = 100;
+const HEIGHT: u32 = 170;
+// not byte aligned, so the mask blit has partial bytes on both ends
+const X: i32 = 333;
+const Y: i32 = 77;
+const ON: u32 = 0x88000000 | (255 * 255);
+const OFF: u32 = 0x88000000;
+
+/// A ring, black and white like the decoded glyphs.
+fn glyph(scale: u32) -> GrayImage {
+    let (width, height) = (WIDTH * scale, HEIGHT * scale);
+    GrayImage::from_fn(width, height, |x, y| {
+        let dx = x as f32 / width as f32 - 0.5;
+        let dy = y as f32 / height as f32 - 0.5;
+        let r = (dx * dx + dy * dy).sqrt();
+        Luma([if (0.3..0.45).contains(&r) { 255 } else { 0 }])
+    })
+}
+
+fn mask_image() -> Image<'static> {
+    Image::allocate(
+        3840,
+        2160,
+        ScanlinePad::Pad8,
+        1,
+        BitsPerPixel::B1,
+        ImageOrder::MsbFirst,
+    )
+}
+
+fn argb_image(glyph: &GrayImage) -> Image<'static> {
+    Image::allocate(
+        glyph.width() as u16,
+        glyph.height() as u16,
+        ScanlinePad::Pad32,
+        32,
+        BitsPerPixel::B32,
+        ImageOrder::MsbFirst,
+    )
+}
+
+fn mask_put_pixel(img: &mut Image, glyph: &GrayImage) {
+    for (x, y, pixel) in glyph.enumerate_pixels() {
+        img.put_pixel(X as u16 + x as u16, Y as u16 + y as u16, pixel.0[0] as u32);
+    }
+}
+
+fn argb_put_pixel(img: &mut Image, glyph: &GrayImage) {
+    for (x, y, pixel) in glyph.enumerate_pixels() {
+        img.put_pixel(x as u16, y as u16, 0x88000000 | (pixel.0[0] as u32 * 255));
+    }
+}
+
+fn mask(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mask");
+    for &scale in SCALES {
+        let glyph = glyph(scale);
+        let (mut old, mut new) = (mask_image(), mask_image());
+        let clip = Clip::of(&new);
+
+        mask_put_pixel(&mut old, &glyph);
+        blit::mask(&mut new, &glyph, X, Y, clip, THRESHOLD);
+        assert!(old.data() == new.data());
+
+        group.bench_function(BenchmarkId::new("put_pixel", scale), |b| {
+            b.iter(|| mask_put_pixel(&mut old, black_box(&glyph)))
+        });
+        group.bench_function(BenchmarkId::new("blit", scale), |b| {
+            b.iter(|| blit::mask(&mut new, black_box(&glyph), X, Y, clip, THRESHOLD))
+        });
+    }
+    group.finish();
+}
+
+fn argb(c: &mut Criterion) {
+    let mut group = c.benchmark_group("argb");
+    for &scale in SCALES {
+        let glyph = glyph(scale);
+        let (mut old, mut new) = (argb_image(&glyph), argb_image(&glyph));
+        let clip = Clip::of(&new);
+
+        argb_put_pixel(&mut old, &glyph);
+        blit::argb(&mut new, &glyph, 0, 0, clip, THRESHOLD, ON, OFF);
+        assert!(old.data() == new.data());
+
+        group.bench_function(BenchmarkId::new("put_pixel", scale), |b| {
+            b.iter(|| argb_put_pixel(&mut old, black_box(&glyph)))
+        });
+        group.bench_function(BenchmarkId::new("blit", scale), |b| {
+            b.iter(|| blit::argb(&mut new, black_box(&glyph), 0, 0, clip, THRESHOLD, ON, OFF))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, mask, argb);
+criterion_main!(benches);
@@ -0,0 +1,54 @@
+// Startup time of the overlay per screen size, from launch until the input region is set, with
+// the mask uploaded through MIT-SHM and over the socket. Needs Xvfb, skipped without.
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+#[path = "../tests/common/xvfb.rs"]
+mod xvfb;
+use xvfb::Xvfb;
+
+/// Laptop up to 4K, then a single screen as wide as two 4K monitors side by side. Xvfb has one
+/// output, so it's still one monitor.
+const SIZES: &[(u16, u16)] = &[
+    (1280, 720),
+    (1920, 1080),
+    (2560, 1440),
+    (3840, 2160),
+    (7680, 2160),
+];
+const RUNS: u32 = 5;
+
+fn startup(display: u16, shm: bool) -> Duration {
+    let start = Instant::now();
+    let status = Command::new(env!("CARGO_BIN_EXE_harlem_shake"))
+        .env("DISPLAY", format!(":{}", display))
+        .arg("--once")
+        .args((!shm).then_some("--no-shm"))
+        .stdout(Stdio::null())
+        .status()
+        .unwrap();
+    assert!(status.success());
+    start.elapsed()
+}
+
+fn main() {
+    println!("{:>11} {:>10} {:>10}", "screen", "shm", "socket");
+    for &(width, height) in SIZES {
+        let Some(xvfb) = Xvfb::start(width, height) else {
+            println!("Xvfb not found, skipping");
+            return;
+        };
+        let mean = |shm| {
+            (0..RUNS)
+                .map(|_| startup(xvfb.display, shm))
+                .sum::<Duration>()
+                / RUNS
+        };
+        println!(
+            "{:>11} {:>8.1}ms {:>8.1}ms",
+            format!("{}x{}", width, height),
+            mean(true).as_secs_f64() * 1000.0,
+            mean(false).as_secs_f64() * 1000.0
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::path::Path;
+
+#[allow(dead_code)]
+#[path = "src/atlas.rs"]
+mod atlas;
+#[allow(dead_code)]
+#[path = "src/crypto.rs"]
+mod crypto;
+#[allow(dead_code)]
+#[path = "src/font.rs"]
+mod font;
+#[allow(dead_code)]
+#[path = "src/layout.rs"]
+mod layout;
+#[allow(dead_code)]
+#[path = "src/manifest.rs"]
+mod manifest;
+#[allow(dead_code)]
+#[path = "src/pack.rs"]
+mod pack;
+
+use layout::{Strategy, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+/// If set, the key and layout seed are derived from this instead of fresh entropy, so a build can
+/// be reproduced. Nonces are derived from key and plaintext, see `crypto::seal`.
+const SEED_VAR: &str = "HARLEM_SHAKE_SEED";
+
+const DEFAULT_MESSAGE: &str = "hxp{Alw4ys_ha5_b3en_oN_y0ur_ScrE3n}";
+
+/// The text drawn on screen.
+const MESSAGE_VAR: &str = "HARLEM_SHAKE_MESSAGE";
+/// How to lay it out: single-line, wrapped or scrambled.
+const LAYOUT_VAR: &str = "HARLEM_SHAKE_LAYOUT";
+/// u64 seed of the layout, taken from the build's randomness if unset. Pinning it keeps the
+/// layout while the key changes.
+const LAYOUT_SEED_VAR: &str = "HARLEM_SHAKE_LAYOUT_SEED";
+/// How far glyphs may be moved up or down, in pixels.
+const JITTER_VAR: &str = "HARLEM_SHAKE_JITTER";
+const DEFAULT_JITTER: u16 = 25;
+/// If set, the layout is written to this file as one `character x y` line per glyph, in drawing
+/// order, to diff layouts between builds.
+const LAYOUT_OUT_VAR: &str = "HARLEM_SHAKE_LAYOUT_OUT";
+/// If set, the message's atlas and manifest are also written to this file as a pack, for
+/// `--pack` of any build with the same HARLEM_SHAKE_SEED.
+const PACK_OUT_VAR: &str = "HARLEM_SHAKE_PACK_OUT";
+
+fn rng() -> ChaCha20Rng {
+    match env::var(SEED_VAR) {
+        Ok(seed) => ChaCha20Rng::from_seed(Sha256::digest(seed.as_bytes()).into()),
+        Err(_) => ChaCha20Rng::from_entropy(),
+    }
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed={}", font::FONT_PATH);
+    println!("cargo:rerun-if-env-changed={}", SEED_VAR);
+    println!("cargo:rerun-if-env-changed={}", MESSAGE_VAR);
+    println!("cargo:rerun-if-env-changed={}", LAYOUT_VAR);
+    println!("cargo:rerun-if-env-changed={}", LAYOUT_SEED_VAR);
+    println!("cargo:rerun-if-env-changed={}", JITTER_VAR);
+    println!("cargo:rerun-if-env-changed={}", LAYOUT_OUT_VAR);
+    println!("cargo:rerun-if-env-changed={}", PACK_OUT_VAR);
+
+    let mut rng = rng();
+    let mut key = [0u8; 32];
+    rng.fill_bytes(&mut key);
+
+    let message = env::var(MESSAGE_VAR).unwrap_or_else(|_| DEFAULT_MESSAGE.to_string());
+    let options = layout::Options {
+        strategy: match env::var(LAYOUT_VAR) {
+            Ok(strategy) => strategy
+                .parse()
+                .unwrap_or_else(|e| panic!("{}: {}", LAYOUT_VAR, e)),
+            Err(_) => Strategy::Scrambled,
+        },
+        seed: match env::var(LAYOUT_SEED_VAR) {
+            Ok(seed) => seed.parse().expect("layout seed has to be a u64"),
+            Err(_) => rng.next_u64(),
+        },
+        jitter: match env::var(JITTER_VAR) {
+            Ok(jitter) => jitter.parse().expect("jitter has to be a u16"),
+            Err(_) => DEFAULT_JITTER,
+        },
+    };
+    let face = font::Face::new();
+    let placements = layout::layout(&message, SCREEN_WIDTH, SCREEN_HEIGHT, &face, &options)
+        .unwrap_or_else(|e| panic!("{:?} does not fit: {}", message, e));
+    let bitmaps: Vec<_> = placements
+        .iter()
+        .map(|p| face.rasterize(p.character))
+        .collect();
+    let glyphs: Vec<_> = placements.iter().map(|p| p.origin).zip(&bitmaps).collect();
+    let problems = layout::validate(&glyphs, SCREEN_WIDTH, SCREEN_HEIGHT);
+    assert!(problems.is_empty(), "bad layout: {:?}", problems);
+
+    if let Ok(path) = env::var(LAYOUT_OUT_VAR) {
+        let mut out = format!("# {:?} {:?}\n", message, options);
+        for placement in &placements {
+            out += &format!(
+                "{} {} {}\n",
+                placement.character, placement.origin.x, placement.origin.y
+            );
+        }
+        fs::write(path, out).unwrap();
+    }
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("key.bin"), key).unwrap();
+
+    let sealed = pack::seal(&key, &placements, &face);
+    if let Ok(path) = env::var(PACK_OUT_VAR) {
+        fs::write(path, sealed.to_pack()).unwrap();
+    }
+
+    let out_dir = Path::new(&out_dir);
+    fs::write(out_dir.join("atlas.bin"), sealed.atlas).unwrap();
+    fs::write(out_dir.join("manifest.bin"), sealed.manifest).unwrap();
+}
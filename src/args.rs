@@ -0,0 +1,165 @@
+use harlem_shake::export::Format;
+use harlem_shake::font::{MAX_FONT_SIZE, MIN_FONT_SIZE};
+use harlem_shake::output::Target;
+use harlem_shake::scale::{DEFAULT_MIN_HEIGHT, DEFAULT_MIN_WIDTH};
+use harlem_shake::stroke::{Stroke, MAX_RADIUS};
+use std::env;
+use std::path::PathBuf;
+
+pub const USAGE: &str =
+    "usage: harlem_shake [--min-size WIDTHxHEIGHT] [--output primary|all|NAME] [--trace]
+                    [--no-shm] [--once] [--threshold LUMA] [--stroke RADIUS]
+                    [--text TEXT [--size PX] | --pack FILE|-]
+       harlem_shake render WIDTHxHEIGHT FILE.png|FILE.pbm|FILE.xbm [--threshold LUMA]
+                    [--stroke RADIUS] [--text TEXT [--size PX] | --pack FILE|-]";
+
+pub enum Command {
+    /// The actual challenge.
+    Overlay,
+    /// Write the input mask for a `width` x `height` screen to `path` instead of showing it.
+    Render {
+        width: u16,
+        height: u16,
+        path: PathBuf,
+        format: Format,
+    },
+}
+
+pub struct Args {
+    pub command: Command,
+    /// Smallest screen we run on, below that the glyphs get too small to read or hover.
+    pub min_width: u16,
+    pub min_height: u16,
+    /// Which RandR output(s) to draw on.
+    pub target: Target,
+    /// Log every X request to stderr.
+    pub trace: bool,
+    /// Upload over the socket even if MIT-SHM is available.
+    pub no_shm: bool,
+    /// Exit after drawing once, for benchmarks.
+    pub once: bool,
+    /// Threshold and dilation or erosion of the glyphs, the overlay's and `render`'s.
+    pub stroke: Stroke,
+    /// Draw this instead of the baked in message, rasterized at runtime.
+    pub text: Option<String>,
+    /// Font size `text` is drawn at, in layout pixels.
+    pub size: Option<f32>,
+    /// Draw the message of this pack instead, `-` reads it from stdin.
+    pub pack: Option<PathBuf>,
+}
+
+impl Args {
+    pub fn parse() -> Result<Args, String> {
+        let mut args = Args {
+            command: Command::Overlay,
+            min_width: DEFAULT_MIN_WIDTH,
+            min_height: DEFAULT_MIN_HEIGHT,
+            target: Target::Primary,
+            trace: false,
+            no_shm: false,
+            once: false,
+            stroke: Stroke::default(),
+            text: None,
+            size: None,
+            pack: None,
+        };
+
+        let mut argv = env::args().skip(1).peekable();
+        if argv.next_if(|arg| arg == "render").is_some() {
+            let size = argv.next().ok_or("render needs a size")?;
+            let (width, height) = parse_size(&size)?;
+            let path = PathBuf::from(argv.next().ok_or("render needs an output file")?);
+            let format = Format::from_path(&path)
+                .ok_or_else(|| format!("can't tell the format of {:?}", path))?;
+            args.command = Command::Render {
+                width,
+                height,
+                path,
+                format,
+            };
+        }
+        let render = matches!(args.command, Command::Render { .. });
+
+        while let Some(arg) = argv.next() {
+            match arg.as_str() {
+                "--threshold" => {
+                    let threshold = argv.next().ok_or("--threshold needs a value")?;
+                    args.stroke.threshold = match threshold.parse() {
+                        Ok(threshold @ 1..=255) => threshold,
+                        _ => {
+                            return Err(format!(
+                                "invalid threshold {:?}, expected 1 to 255",
+                                threshold
+                            ))
+                        }
+                    };
+                }
+                "--stroke" => {
+                    let radius = argv.next().ok_or("--stroke needs a value")?;
+                    args.stroke.radius = match radius.parse() {
+                        Ok(radius) if (-MAX_RADIUS..=MAX_RADIUS).contains(&radius) => radius,
+                        _ => {
+                            return Err(format!(
+                                "invalid stroke radius {:?}, expected {} to {}",
+                                radius, -MAX_RADIUS, MAX_RADIUS
+                            ))
+                        }
+                    };
+                }
+                "--text" => args.text = Some(argv.next().ok_or("--text needs a value")?),
+                "--size" => {
+                    let size = argv.next().ok_or("--size needs a value")?;
+                    args.size = match size.parse() {
+                        Ok(size) if (MIN_FONT_SIZE..=MAX_FONT_SIZE).contains(&size) => Some(size),
+                        _ => {
+                            return Err(format!(
+                                "invalid font size {:?}, expected {} to {}",
+                                size, MIN_FONT_SIZE, MAX_FONT_SIZE
+                            ))
+                        }
+                    };
+                }
+                "--pack" => args.pack = Some(argv.next().ok_or("--pack needs a value")?.into()),
+                // the rest is about the window
+                _ if render => return Err(format!("unknown argument {:?}", arg)),
+                "--min-size" => {
+                    let size = argv.next().ok_or("--min-size needs a value")?;
+                    (args.min_width, args.min_height) = parse_size(&size)?;
+                }
+                "--output" => {
+                    args.target = match argv.next().ok_or("--output needs a value")?.as_str() {
+                        "primary" => Target::Primary,
+                        "all" => Target::All,
+                        name => Target::Output(name.to_string()),
+                    };
+                }
+                "--trace" => args.trace = true,
+                "--no-shm" => args.no_shm = true,
+                "--once" => args.once = true,
+                _ => return Err(format!("unknown argument {:?}", arg)),
+            }
+        }
+        if args.text.is_some() && args.pack.is_some() {
+            return Err("--text and --pack can't be used together".to_string());
+        }
+        // the baked in message and packs come rasterized already
+        if args.size.is_some() && args.text.is_none() {
+            return Err("--size only works with --text".to_string());
+        }
+        Ok(args)
+    }
+}
+
+/// `1920x1080` -> `(1920, 1080)`
+fn parse_size(size: &str) -> Result<(u16, u16), String> {
+    let invalid = || format!("invalid size {:?}, expected WIDTHxHEIGHT", size);
+    let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+    let (width, height) = (
+        width.parse().map_err(|_| invalid())?,
+        height.parse().map_err(|_| invalid())?,
+    );
+    if width == 0 || height == 0 {
+        return Err(format!("invalid size {:?}, can't be empty", size));
+    }
+    Ok((width, height))
+}
@@ -0,0 +1,303 @@
+// Shared between build.rs, which writes the glyph atlas, and main.rs, which reads it.
+//
+// Every character the message uses is drawn once into a single atlas image. The container is a
+// short plaintext header, the index of the atlas sealed with the atlas key and the header as
+// associated data, then every slot's pixels sealed on their own (nonce || ciphertext || tag each).
+// All little endian:
+//
+//   header:  magic "HSGA", version: u16, reserved: u16 (0), length after the header: u32,
+//            index length: u32
+//   index:   count: u32, width: u16, height: u16, count entries
+//   entry:   x: u16, y: u16, width: u16, height: u16, bearing_x: i16, bearing_y: i16,
+//            advance: f32, blob length: u32
+//   glyphs:  count blobs back to back
+//   blob:    character: u32, then the deflated bitmap
+//
+// x and y locate the glyph's bitmap in the atlas, width to advance are its `Metrics`. Which
+// character a slot holds is only in its blob, the index and the manifest just count slots. Blob i
+// is sealed with `crypto::slot_key(master, i, x, y)` and its index entry as associated data, so it
+// only opens in its own slot, under its own metrics, and one key only reveals one character. The
+// bitmap is the glyph at 1 bit per pixel, most significant bit first, every row padded to a whole
+// byte, deflated before encryption. Glyphs are black and white anyway, and that way opening an
+// atlas needs no image decoder.
+use crate::crypto;
+use crate::layout::Metrics;
+use image::{GrayImage, Luma};
+use miniz_oxide::deflate::compress_to_vec;
+use miniz_oxide::inflate::decompress_to_vec_with_limit;
+use std::fmt;
+
+pub const MAGIC: &[u8; 4] = b"HSGA";
+/// 1 had the atlas as one PNG, 2 deflated it as a whole, both under the atlas key alone. 3 kept
+/// the characters in the index.
+pub const VERSION: u16 = 4;
+pub const HEADER_LEN: usize = 16;
+/// count, width and height.
+const INDEX_HEADER_LEN: usize = 8;
+pub const INDEX_ENTRY_LEN: usize = 20;
+/// Pixels from this up are ink.
+const INK: u8 = 128;
+
+/// Glyphs are packed into rows no wider than this.
+const ROW_WIDTH: u32 = 1024;
+
+#[derive(Debug)]
+pub enum AtlasError {
+    /// Doesn't start with the magic, has reserved bits set, or is cut short.
+    NotAnAtlas,
+    /// A format version other than `VERSION`. Older atlases and packs aren't read either, they
+    /// have to be built again.
+    Version(u16),
+    /// The index was tampered with, or is for another key.
+    IndexAuthFailed,
+    /// Glyph `index` was tampered with or moved.
+    AuthFailed { index: usize },
+    /// Authentic, but the index or a glyph don't make sense.
+    Corrupt,
+}
+
+impl fmt::Display for AtlasError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AtlasError::NotAnAtlas => write!(f, "not a glyph atlas"),
+            AtlasError::Version(version) => write!(
+                f,
+                "atlas format version {} can't be read, only version {}, rebuild it",
+                version, VERSION
+            ),
+            AtlasError::IndexAuthFailed => write!(f, "atlas index fails to authenticate"),
+            AtlasError::AuthFailed { index } => {
+                write!(f, "glyph {} of the atlas fails to authenticate", index)
+            }
+            AtlasError::Corrupt => write!(f, "atlas is corrupt"),
+        }
+    }
+}
+
+/// Where a character's glyph is in the atlas, and its metrics.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Slot {
+    pub character: char,
+    pub x: u16,
+    pub y: u16,
+    pub metrics: Metrics,
+}
+
+pub struct Atlas {
+    pub slots: Vec<Slot>,
+    pub image: GrayImage,
+}
+
+impl Atlas {
+    /// Pack glyphs left to right into rows, in the order given.
+    pub fn pack(glyphs: &[(char, Metrics, GrayImage)]) -> Atlas {
+        let (mut x, mut y, mut row_height, mut width) = (0, 0, 0, 0);
+        let mut slots = vec![];
+        for (character, metrics, bitmap) in glyphs {
+            if x > 0 && x + bitmap.width() > ROW_WIDTH {
+                (x, y, row_height) = (0, y + row_height, 0);
+            }
+            slots.push(Slot {
+                character: *character,
+                x: x as u16,
+                y: y as u16,
+                metrics: *metrics,
+            });
+            x += bitmap.width();
+            width = width.max(x);
+            row_height = row_height.max(bitmap.height());
+        }
+
+        let mut image = GrayImage::new(width.max(1), (y + row_height).max(1));
+        for (slot, (_, _, bitmap)) in slots.iter().zip(glyphs) {
+            image::imageops::replace(&mut image, bitmap, slot.x as i64, slot.y as i64);
+        }
+        Atlas { slots, image }
+    }
+
+    pub fn slot(&self, character: char) -> Option<&Slot> {
+        self.slots.iter().find(|slot| slot.character == character)
+    }
+
+    /// `character`'s bitmap, `None` if the atlas doesn't have it.
+    pub fn glyph(&self, character: char) -> Option<GrayImage> {
+        let slot = self.slot(character)?;
+        Some(self.bitmap(slot))
+    }
+
+    /// The bitmap in slot `index`, `None` past the last one.
+    pub fn glyph_at(&self, index: usize) -> Option<GrayImage> {
+        Some(self.bitmap(self.slots.get(index)?))
+    }
+
+    fn bitmap(&self, slot: &Slot) -> GrayImage {
+        image::imageops::crop_imm(
+            &self.image,
+            slot.x as u32,
+            slot.y as u32,
+            slot.metrics.width as u32,
+            slot.metrics.height as u32,
+        )
+        .to_image()
+    }
+
+    /// The whole container, sealed with keys derived from `master`.
+    pub fn seal(&self, master: &[u8; 32]) -> Vec<u8> {
+        let deflated: Vec<_> = self
+            .slots
+            .iter()
+            .map(|slot| {
+                let mut blob = (slot.character as u32).to_le_bytes().to_vec();
+                blob.extend(compress_to_vec(&bits(&self.bitmap(slot)), 10));
+                blob
+            })
+            .collect();
+        let sealed_len = |plain: usize| crypto::NONCE_LEN + plain + crypto::TAG_LEN;
+
+        let mut index = (self.slots.len() as u32).to_le_bytes().to_vec();
+        index.extend((self.image.width() as u16).to_le_bytes());
+        index.extend((self.image.height() as u16).to_le_bytes());
+        let mut entries = vec![];
+        for (slot, glyph) in self.slots.iter().zip(&deflated) {
+            let metrics = &slot.metrics;
+            let mut entry = slot.x.to_le_bytes().to_vec();
+            entry.extend(slot.y.to_le_bytes());
+            entry.extend(metrics.width.to_le_bytes());
+            entry.extend(metrics.height.to_le_bytes());
+            entry.extend(metrics.bearing_x.to_le_bytes());
+            entry.extend(metrics.bearing_y.to_le_bytes());
+            entry.extend(metrics.advance.to_le_bytes());
+            entry.extend((sealed_len(glyph.len()) as u32).to_le_bytes());
+            index.extend(&entry);
+            entries.push(entry);
+        }
+
+        let index_len = sealed_len(index.len());
+        let len = index_len + deflated.iter().map(|g| sealed_len(g.len())).sum::<usize>();
+        let mut out = MAGIC.to_vec();
+        out.extend(VERSION.to_le_bytes());
+        out.extend(0u16.to_le_bytes());
+        out.extend((len as u32).to_le_bytes());
+        out.extend((index_len as u32).to_le_bytes());
+        // the header can't be changed without the index failing to open
+        let index = crypto::seal(&crypto::atlas_key(master), &out, &index);
+        out.extend(index);
+        for (i, ((slot, entry), glyph)) in
+            self.slots.iter().zip(&entries).zip(&deflated).enumerate()
+        {
+            let key = crypto::slot_key(master, i, slot.x, slot.y);
+            out.extend(crypto::seal(&key, entry, glyph));
+        }
+        out
+    }
+
+    /// Length of the container at the start of `data`, header included.
+    pub fn len(data: &[u8]) -> Result<usize, AtlasError> {
+        let header = data.get(..HEADER_LEN).ok_or(AtlasError::NotAnAtlas)?;
+        if &header[..4] != MAGIC {
+            return Err(AtlasError::NotAnAtlas);
+        }
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version != VERSION {
+            return Err(AtlasError::Version(version));
+        }
+        if header[6..8] != [0, 0] {
+            return Err(AtlasError::NotAnAtlas);
+        }
+        let len = u32::from_le_bytes(header[8..12].try_into().unwrap()) as usize;
+        Ok(HEADER_LEN + len)
+    }
+
+    /// Check and decrypt the container at the start of `data`, with keys derived from `master`.
+    pub fn open(data: &[u8], master: &[u8; 32]) -> Result<Atlas, AtlasError> {
+        let body = data
+            .get(HEADER_LEN..Atlas::len(data)?)
+            .ok_or(AtlasError::NotAnAtlas)?;
+        let index_len = u32::from_le_bytes(data[12..16].try_into().unwrap()) as usize;
+        let (index, mut glyphs) = body
+            .split_at_checked(index_len)
+            .ok_or(AtlasError::NotAnAtlas)?;
+        let index = crypto::open(&crypto::atlas_key(master), &data[..HEADER_LEN], index)
+            .ok_or(AtlasError::IndexAuthFailed)?;
+
+        let header = index.get(..INDEX_HEADER_LEN).ok_or(AtlasError::Corrupt)?;
+        let count = u32::from_le_bytes(header[0..4].try_into().unwrap()) as usize;
+        let width = u16::from_le_bytes([header[4], header[5]]) as u32;
+        let height = u16::from_le_bytes([header[6], header[7]]) as u32;
+        if index.len() != count.saturating_mul(INDEX_ENTRY_LEN) + INDEX_HEADER_LEN {
+            return Err(AtlasError::Corrupt);
+        }
+
+        let mut slots = vec![];
+        let mut image = GrayImage::new(width, height);
+        for (i, e) in index[INDEX_HEADER_LEN..]
+            .chunks_exact(INDEX_ENTRY_LEN)
+            .enumerate()
+        {
+            let u16_at = |at: usize| u16::from_le_bytes([e[at], e[at + 1]]);
+            let (x, y) = (u16_at(0), u16_at(2));
+            let metrics = Metrics {
+                width: u16_at(4),
+                height: u16_at(6),
+                bearing_x: u16_at(8) as i16,
+                bearing_y: u16_at(10) as i16,
+                advance: f32::from_le_bytes(e[12..16].try_into().unwrap()),
+            };
+            let (w, h) = (metrics.width as u32, metrics.height as u32);
+            // every glyph has to be inside the image
+            if x as u32 + w > width || y as u32 + h > height {
+                return Err(AtlasError::Corrupt);
+            }
+
+            let len = u32::from_le_bytes(e[16..20].try_into().unwrap()) as usize;
+            let (blob, rest) = glyphs.split_at_checked(len).ok_or(AtlasError::Corrupt)?;
+            glyphs = rest;
+            let key = crypto::slot_key(master, i, x, y);
+            let blob = crypto::open(&key, e, blob).ok_or(AtlasError::AuthFailed { index: i })?;
+            let (character, deflated) = blob.split_at_checked(4).ok_or(AtlasError::Corrupt)?;
+            let slot = Slot {
+                character: char::from_u32(u32::from_le_bytes(character.try_into().unwrap()))
+                    .ok_or(AtlasError::Corrupt)?,
+                x,
+                y,
+                metrics,
+            };
+            let size = row_len(w) * h as usize;
+            let bits = decompress_to_vec_with_limit(deflated, size)
+                .ok()
+                .filter(|bits| bits.len() == size)
+                .ok_or(AtlasError::Corrupt)?;
+
+            let row = row_len(w);
+            for y in 0..h {
+                for x in 0..w {
+                    if bits[y as usize * row + x as usize / 8] & (0x80 >> (x % 8)) != 0 {
+                        image.put_pixel(slot.x as u32 + x, slot.y as u32 + y, Luma([255]));
+                    }
+                }
+            }
+            slots.push(slot);
+        }
+        if !glyphs.is_empty() {
+            return Err(AtlasError::Corrupt);
+        }
+        Ok(Atlas { slots, image })
+    }
+}
+
+/// `glyph` at 1 bit per pixel.
+fn bits(glyph: &GrayImage) -> Vec<u8> {
+    let row = row_len(glyph.width());
+    let mut bits = vec![0u8; row * glyph.height() as usize];
+    for (x, y, pixel) in glyph.enumerate_pixels() {
+        if pixel.0[0] >= INK {
+            bits[y as usize * row + x as usize / 8] |= 0x80 >> (x % 8);
+        }
+    }
+    bits
+}
+
+/// Bytes of a 1-bit row `width` pixels wide.
+fn row_len(width: u32) -> usize {
+    (width as usize).div_ceil(8)
+}
@@ -0,0 +1,246 @@
+//! Recovers the flag from a built (and stripped) harlem_shake binary, no offsets needed:
+//!
+//! 1. Every `lea reg, [rip + x]` in .text and every relocated pointer, as debug builds keep the
+//!    statics around, that points at random looking bytes in .rodata is a candidate for the key
+//!    and the manifest.
+//! 2. The glyph atlas starts with its magic, the right key opens it.
+//! 3. A manifest is right if it decrypts to entries of on-screen positions whose tickets open
+//!    at their index and position to slots the atlas has, and the tag checks out with the atlas
+//!    header and digest as associated data. Which character is drawn where then comes from the
+//!    slots.
+//!
+//! usage: solve BINARY [FLAG.png]
+use chacha20poly1305::aead::{AeadInPlace, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use harlem_shake::atlas::{self, Atlas};
+use harlem_shake::crypto::{self, NONCE_LEN, TAG_LEN};
+use harlem_shake::layout::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use harlem_shake::manifest::{self, Entry, ENTRY_LEN};
+use harlem_shake::pack;
+use image::GrayImage;
+use object::{Object, ObjectSection, RelocationTarget};
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::process::ExitCode;
+
+/// Out of 32, random bytes have ~28 different values, strings and tables far fewer.
+const MIN_DISTINCT: usize = 26;
+
+/// Offsets into `rodata` of everything .text loads the address of.
+fn lea_targets(text: &[u8], text_addr: u64, rodata_addr: u64, rodata_len: usize) -> Vec<usize> {
+    let mut targets = vec![];
+    for (i, insn) in text.windows(7).enumerate() {
+        // REX.W (+R) 8D /r with mod=00 rm=101: lea r64, [rip + disp32]
+        if !matches!(insn[0], 0x48 | 0x4c) || insn[1] != 0x8d || insn[2] & 0xc7 != 0x05 {
+            continue;
+        }
+        let disp = i32::from_le_bytes([insn[3], insn[4], insn[5], insn[6]]);
+        let target = (text_addr + i as u64 + 7).wrapping_add_signed(disp as i64);
+        if let Some(offset) = target.checked_sub(rodata_addr) {
+            if offset < rodata_len as u64 {
+                targets.push(offset as usize);
+            }
+        }
+    }
+    targets
+}
+
+/// Offsets into `rodata` of every pointer the dynamic loader relocates, e.g. `static X: &[u8]`.
+fn pointer_targets(elf: &object::File, rodata_addr: u64, rodata_len: usize) -> Vec<usize> {
+    let Some(relocations) = elf.dynamic_relocations() else {
+        return vec![];
+    };
+    relocations
+        .filter(|(_, r)| r.target() == RelocationTarget::Absolute)
+        .filter_map(|(_, r)| (r.addend() as u64).checked_sub(rodata_addr))
+        .filter(|&offset| offset < rodata_len as u64)
+        .map(|offset| offset as usize)
+        .collect()
+}
+
+fn looks_random(data: &[u8]) -> bool {
+    let Some(window) = data.get(..32) else {
+        return false;
+    };
+    let mut seen = [false; 256];
+    window.iter().for_each(|&b| seen[b as usize] = true);
+    seen.iter().filter(|&&s| s).count() >= MIN_DISTINCT
+}
+
+struct Found {
+    key_at: usize,
+    manifest_at: usize,
+    entries: Vec<Entry>,
+    atlas_at: usize,
+    atlas: Atlas,
+}
+
+/// Decrypt the first `len` bytes of a sealed blob without checking the tag.
+fn peek(key: &Key, sealed: &[u8], len: usize) -> Option<Vec<u8>> {
+    let nonce = Nonce::from_slice(sealed.get(..NONCE_LEN)?);
+    let mut plain = sealed.get(NONCE_LEN..NONCE_LEN + len)?.to_vec();
+    // ChaCha20 is symmetric, encrypting applies the same keystream
+    ChaCha20Poly1305::new(key)
+        .encrypt_in_place_detached(nonce, b"", &mut plain)
+        .ok()?;
+    Some(plain)
+}
+
+/// How many entries at the start of the decrypted `plain` form a plausible manifest under
+/// `master` for an atlas of `slots` slots.
+fn valid_len(plain: &[u8], master: &[u8; 32], slots: usize) -> usize {
+    plain
+        .chunks_exact(ENTRY_LEN)
+        .enumerate()
+        .take_while(|&(index, chunk)| {
+            manifest::decode_entry(index, chunk, master).is_some_and(|entry| {
+                (0..SCREEN_WIDTH as i16).contains(&entry.x)
+                    && (0..SCREEN_HEIGHT as i16).contains(&entry.y)
+                    && (entry.slot as usize) < slots
+            })
+        })
+        .count()
+}
+
+/// The glyph atlas `master` opens, with where it starts and what the manifest is sealed with.
+fn find_atlas(rodata: &[u8], master: &[u8; 32]) -> Option<(usize, Atlas, Vec<u8>)> {
+    rodata
+        .windows(atlas::MAGIC.len())
+        .enumerate()
+        .filter(|(_, window)| window == atlas::MAGIC)
+        .find_map(|(at, _)| {
+            let atlas = Atlas::open(&rodata[at..], master).ok()?;
+            let container = &rodata[at..at + Atlas::len(&rodata[at..]).ok()?];
+            Some((at, atlas, pack::manifest_aad(container)))
+        })
+}
+
+/// The master key, the atlas and the decrypted manifest, with where they were found.
+fn find_manifest(rodata: &[u8], candidates: &[usize]) -> Option<Found> {
+    for &key_at in candidates {
+        let master: [u8; 32] = rodata[key_at..key_at + 32].try_into().unwrap();
+        let Some((atlas_at, atlas, aad)) = find_atlas(rodata, &master) else {
+            continue;
+        };
+        let key = crypto::manifest_key(&master);
+        for &manifest_at in candidates {
+            let sealed = &rodata[manifest_at..];
+            // one entry is enough to tell
+            let Some(first) = peek(&key, sealed, ENTRY_LEN) else {
+                continue;
+            };
+            if valid_len(&first, &master, atlas.slots.len()) == 0 {
+                continue;
+            }
+
+            let max = sealed.len().saturating_sub(NONCE_LEN + TAG_LEN) / ENTRY_LEN * ENTRY_LEN;
+            let Some(plain) = peek(&key, sealed, max) else {
+                continue;
+            };
+            for count in (1..=valid_len(&plain, &master, atlas.slots.len())).rev() {
+                let len = NONCE_LEN + count * ENTRY_LEN + TAG_LEN;
+                if let Some(plain) = crypto::open(&key, &aad, &sealed[..len]) {
+                    return Some(Found {
+                        key_at,
+                        manifest_at,
+                        entries: manifest::decode(&plain, &master).ok()?,
+                        atlas_at,
+                        atlas,
+                    });
+                }
+            }
+        }
+    }
+    None
+}
+
+fn solve(binary: &[u8]) -> Result<GrayImage, String> {
+    let elf = object::File::parse(binary).map_err(|e| format!("not an ELF file: {}", e))?;
+    let section = |name| {
+        let section = elf
+            .section_by_name(name)
+            .ok_or(format!("no {} section", name))?;
+        let data = section
+            .data()
+            .map_err(|e| format!("can't read {}: {}", name, e))?;
+        Ok::<_, String>((section.address(), data))
+    };
+    let (text_addr, text) = section(".text")?;
+    let (rodata_addr, rodata) = section(".rodata")?;
+
+    let mut candidates = BTreeSet::new();
+    candidates.extend(lea_targets(text, text_addr, rodata_addr, rodata.len()));
+    candidates.extend(pointer_targets(&elf, rodata_addr, rodata.len()));
+    let candidates: Vec<usize> = candidates
+        .into_iter()
+        .filter(|&offset| looks_random(&rodata[offset..]))
+        .collect();
+    println!("{} candidate blobs in .rodata", candidates.len());
+
+    let Found {
+        key_at,
+        manifest_at,
+        entries,
+        atlas_at,
+        atlas,
+    } = find_manifest(rodata, &candidates).ok_or("no key opens an atlas and a manifest")?;
+    println!("key at {:#x}", rodata_addr + key_at as u64);
+    println!(
+        "atlas at {:#x}, {} characters",
+        rodata_addr + atlas_at as u64,
+        atlas.slots.len()
+    );
+    println!(
+        "manifest at {:#x}, {} glyphs",
+        rodata_addr + manifest_at as u64,
+        entries.len()
+    );
+
+    let mut flag = GrayImage::new(SCREEN_WIDTH as u32, SCREEN_HEIGHT as u32);
+    for (index, entry) in entries.iter().enumerate() {
+        let glyph = atlas
+            .glyph_at(entry.slot as usize)
+            .ok_or(format!("glyph {} is not in the atlas", index))?;
+        // boxes may overlap, don't let one glyph's background erase another
+        for (x, y, pixel) in glyph.enumerate_pixels() {
+            let (x, y) = (entry.x as i64 + x as i64, entry.y as i64 + y as i64);
+            if x >= 0
+                && y >= 0
+                && x < flag.width() as i64
+                && y < flag.height() as i64
+                && pixel.0[0] != 0
+            {
+                flag.put_pixel(x as u32, y as u32, *pixel);
+            }
+        }
+    }
+    Ok(flag)
+}
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let (Some(binary), out) = (args.next(), args.next()) else {
+        println!("usage: solve BINARY [FLAG.png]");
+        return ExitCode::from(2);
+    };
+    let out = out.unwrap_or_else(|| "flag.png".to_string());
+
+    let result = fs::read(&binary)
+        .map_err(|e| format!("can't read {}: {}", binary, e))
+        .and_then(|binary| solve(&binary))
+        .and_then(|flag| {
+            flag.save(&out)
+                .map_err(|e| format!("can't write {}: {}", out, e))
+        });
+    match result {
+        Ok(()) => {
+            println!("Wrote {}", out);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            println!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
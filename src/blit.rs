@@ -0,0 +1,134 @@
+// Copies luma glyphs into X images a scanline at a time. Going through `Image::put_pixel` costs a
+// bounds check, a stride computation and a byte order match for every single pixel.
+use image::GrayImage;
+use std::ops::Range;
+use x11rb::image::{BitsPerPixel, Image, ImageOrder};
+
+/// Part of the target image that may be written to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Clip {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Clip {
+    /// All of `image`.
+    pub fn of(image: &Image) -> Clip {
+        Clip {
+            x: 0,
+            y: 0,
+            width: image.width() as u32,
+            height: image.height() as u32,
+        }
+    }
+}
+
+/// The glyph columns and rows that land inside both the clip and the image, and the target
+/// pixel the first of them goes to.
+struct Span {
+    columns: Range<usize>,
+    rows: Range<usize>,
+    x: usize,
+    y: usize,
+}
+
+fn span(image: &Image, glyph: &GrayImage, x: i32, y: i32, clip: Clip) -> Option<Span> {
+    let axis = |at: i32, len: u32, clip_at: i32, clip_len: u32, image_len: u16| {
+        let (at, clip_at) = (at as i64, clip_at as i64);
+        let start = at.max(clip_at).max(0);
+        let end = (at + len as i64)
+            .min(clip_at + clip_len as i64)
+            .min(image_len as i64);
+        (start < end).then(|| ((start - at) as usize..(end - at) as usize, start as usize))
+    };
+    let (columns, x) = axis(x, glyph.width(), clip.x, clip.width, image.width())?;
+    let (rows, y) = axis(y, glyph.height(), clip.y, clip.height, image.height())?;
+    Some(Span {
+        columns,
+        rows,
+        x,
+        y,
+    })
+}
+
+/// The glyph's rows that are drawn, each paired with the target scanline it goes into.
+fn rows<'a>(
+    image: &'a mut Image,
+    glyph: &'a GrayImage,
+    span: &'a Span,
+) -> impl Iterator<Item = (&'a [u8], &'a mut [u8])> + 'a {
+    // allocate() makes exactly height scanlines, so this is the padded stride
+    let stride = image.data().len() / image.height() as usize;
+    let width = glyph.width() as usize;
+    let source = glyph.as_raw().chunks_exact(width).skip(span.rows.start);
+    let target = image.data_mut().chunks_exact_mut(stride).skip(span.y);
+    source
+        .zip(target)
+        .take(span.rows.len())
+        .map(move |(source, target)| (&source[span.columns.clone()], target))
+}
+
+/// Set the bits of a 1 bit per pixel `image` where `glyph`, placed at `x`, `y`, is at least
+/// `threshold`. Other bits are left alone, so glyphs can share bytes.
+pub fn mask(image: &mut Image, glyph: &GrayImage, x: i32, y: i32, clip: Clip, threshold: u8) {
+    assert!(image.bits_per_pixel() == BitsPerPixel::B1);
+    let Some(span) = span(image, glyph, x, y, clip) else {
+        return;
+    };
+    let bit: fn(usize) -> u8 = match image.byte_order() {
+        ImageOrder::MsbFirst => |column: usize| 0x80u8 >> (column % 8),
+        ImageOrder::LsbFirst => |column: usize| 1u8 << (column % 8),
+    };
+
+    let first = span.x;
+    for (source, target) in rows(image, glyph, &span) {
+        // collect a byte's worth of bits before touching the scanline
+        let mut byte = 0;
+        for (column, &luma) in (first..).zip(source) {
+            if luma >= threshold {
+                byte |= bit(column);
+            }
+            if column % 8 == 7 {
+                target[column / 8] |= byte;
+                byte = 0;
+            }
+        }
+        let end = first + source.len();
+        if end % 8 != 0 {
+            target[end / 8] |= byte;
+        }
+    }
+}
+
+/// Write `on` into a 32 bits per pixel `image` where `glyph`, placed at `x`, `y`, is at least
+/// `threshold`, and `off` everywhere else under the glyph.
+#[allow(clippy::too_many_arguments)]
+pub fn argb(
+    image: &mut Image,
+    glyph: &GrayImage,
+    x: i32,
+    y: i32,
+    clip: Clip,
+    threshold: u8,
+    on: u32,
+    off: u32,
+) {
+    assert!(image.bits_per_pixel() == BitsPerPixel::B32);
+    let Some(span) = span(image, glyph, x, y, clip) else {
+        return;
+    };
+    let (on, off) = match image.byte_order() {
+        ImageOrder::MsbFirst => (on.to_be_bytes(), off.to_be_bytes()),
+        ImageOrder::LsbFirst => (on.to_le_bytes(), off.to_le_bytes()),
+    };
+
+    let first = span.x * 4;
+    for (source, target) in rows(image, glyph, &span) {
+        let target = &mut target[first..first + source.len() * 4];
+        for (pixel, &luma) in target.chunks_exact_mut(4).zip(source) {
+            pixel.copy_from_slice(if luma >= threshold { &on } else { &off });
+        }
+    }
+}
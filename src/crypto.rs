@@ -0,0 +1,99 @@
+// Shared between build.rs, which encrypts the assets, and main.rs, which decrypts them.
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use hkdf::Hkdf;
+use sha2::Sha256;
+
+pub const NONCE_LEN: usize = 12;
+pub const TAG_LEN: usize = 16;
+
+fn derive(master: &[u8; 32], info: &[u8]) -> Key {
+    let mut key = Key::default();
+    Hkdf::<Sha256>::new(None, master)
+        .expand(info, &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    key
+}
+
+/// Key of the manifest, the encrypted list of glyph positions.
+pub fn manifest_key(master: &[u8; 32]) -> Key {
+    derive(master, b"harlem_shake manifest")
+}
+
+/// Key of the glyph atlas index, where every slot is and its metrics.
+pub fn atlas_key(master: &[u8; 32]) -> Key {
+    derive(master, b"harlem_shake atlas")
+}
+
+/// Key of one slot of the glyph atlas, its character and pixels. Binding it to the slot's index
+/// and place in the atlas means a blob only decrypts in the slot it was built for, and one key
+/// only reveals one character.
+pub fn slot_key(master: &[u8; 32], index: usize, x: u16, y: u16) -> Key {
+    let mut info = b"harlem_shake slot".to_vec();
+    info.extend((index as u32).to_le_bytes());
+    info.extend(x.to_le_bytes());
+    info.extend(y.to_le_bytes());
+    derive(master, &info)
+}
+
+/// Key of the ticket of manifest entry `index`, which says what atlas slot is drawn at `x`, `y`.
+/// Binding it to the placement means a glyph moved or reordered by someone holding the manifest
+/// key no longer gets a slot.
+pub fn glyph_key(master: &[u8; 32], index: usize, x: i16, y: i16) -> Key {
+    let mut info = b"harlem_shake glyph".to_vec();
+    info.extend((index as u32).to_le_bytes());
+    info.extend(x.to_le_bytes());
+    info.extend(y.to_le_bytes());
+    derive(master, &info)
+}
+
+/// The nonce `plain` is sealed with: a PRF of key, associated data and plaintext, so one key never
+/// gets the same nonce for different inputs, and a seeded build still comes out the same every
+/// time.
+fn nonce(key: &Key, aad: &[u8], plain: &[u8]) -> [u8; NONCE_LEN] {
+    // HMAC-SHA256 of both, under a key of its own
+    let mut mac_key = [0u8; 32];
+    Hkdf::<Sha256>::from_prk(key)
+        .expect("32 bytes is a valid HKDF-SHA256 PRK")
+        .expand(b"harlem_shake nonce", &mut mac_key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    let mut input = (aad.len() as u64).to_le_bytes().to_vec();
+    input.extend(aad);
+    input.extend(plain);
+    let (mac, _) = Hkdf::<Sha256>::extract(Some(&mac_key), &input);
+    mac[..NONCE_LEN].try_into().unwrap()
+}
+
+/// Encrypt to nonce || ciphertext || tag. `aad` isn't stored, but has to be passed to `open` as
+/// is.
+pub fn seal(key: &Key, aad: &[u8], plain: &[u8]) -> Vec<u8> {
+    let nonce = nonce(key, aad, plain);
+    let encrypted = ChaCha20Poly1305::new(key)
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: plain, aad })
+        .expect("encryption failed");
+
+    let mut out = nonce.to_vec();
+    out.extend(encrypted);
+    out
+}
+
+/// Decrypt nonce || ciphertext || tag, `None` if the blob or `aad` was tampered with.
+pub fn open(key: &Key, aad: &[u8], sealed: &[u8]) -> Option<Vec<u8>> {
+    if sealed.len() < NONCE_LEN {
+        return None;
+    }
+    let (nonce, encrypted) = sealed.split_at(NONCE_LEN);
+
+    // has to consist of encrypted bytes + tag
+    ChaCha20Poly1305::new(key)
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: encrypted,
+                aad,
+            },
+        )
+        .ok()
+}
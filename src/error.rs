@@ -0,0 +1,192 @@
+use harlem_shake::atlas::AtlasError;
+use harlem_shake::layout::{LayoutError, Problem};
+use harlem_shake::output::NoOutput;
+use std::path::PathBuf;
+use std::{fmt, io};
+use x11rb::errors::{ConnectError, ConnectionError, ReplyError, ReplyOrIdError};
+
+/// Everything that stops the overlay. Each variant exits with its own code, so wrapper scripts
+/// can tell them apart.
+#[derive(Debug)]
+pub enum OverlayError {
+    Usage(String),
+    Connect(ConnectError),
+    NoRender,
+    NoArgbVisual,
+    NoCompositor,
+    NoOutput {
+        name: String,
+        available: Vec<String>,
+    },
+    ScreenTooSmall {
+        min_width: u16,
+        min_height: u16,
+    },
+    ManifestAuthFailed,
+    Atlas(AtlasError),
+    /// The manifest wants a slot the atlas doesn't have.
+    InvalidGlyph {
+        index: usize,
+    },
+    /// A manifest entry's ticket doesn't open where it's placed.
+    MovedGlyph {
+        index: usize,
+    },
+    XProtocol(ReplyOrIdError),
+    /// The manifest places glyphs off screen, in the margin or on top of each other.
+    InvalidLayout(Vec<Problem>),
+    /// `--stroke` grows the glyphs into each other or the margin.
+    InvalidStroke {
+        radius: i16,
+        problems: Vec<Problem>,
+    },
+    /// `--text` doesn't fit on the screen.
+    TextLayout(LayoutError),
+    /// `--text` has a character the font can't draw.
+    MissingGlyph(char),
+    /// `--pack` couldn't be read.
+    Pack {
+        path: PathBuf,
+        error: io::Error,
+    },
+    /// `render` couldn't write its output.
+    Export {
+        path: PathBuf,
+        error: io::Error,
+    },
+}
+
+impl OverlayError {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            OverlayError::Usage(_) => 2,
+            OverlayError::Connect(_) => 3,
+            OverlayError::NoRender => 4,
+            OverlayError::NoArgbVisual => 5,
+            OverlayError::NoCompositor => 6,
+            OverlayError::NoOutput { .. } => 7,
+            OverlayError::ScreenTooSmall { .. } => 8,
+            OverlayError::ManifestAuthFailed => 9,
+            OverlayError::Atlas(AtlasError::AuthFailed { .. }) => 10,
+            OverlayError::InvalidGlyph { .. } => 11,
+            OverlayError::XProtocol(_) => 12,
+            OverlayError::Export { .. } => 13,
+            OverlayError::InvalidLayout(_) => 14,
+            OverlayError::TextLayout(_) => 15,
+            OverlayError::MissingGlyph(_) => 16,
+            OverlayError::Pack { .. } => 17,
+            OverlayError::MovedGlyph { .. } => 18,
+            OverlayError::Atlas(AtlasError::NotAnAtlas) => 19,
+            OverlayError::Atlas(AtlasError::Version(_)) => 20,
+            OverlayError::Atlas(AtlasError::IndexAuthFailed) => 21,
+            OverlayError::Atlas(AtlasError::Corrupt) => 22,
+            OverlayError::InvalidStroke { .. } => 23,
+        }
+    }
+}
+
+impl fmt::Display for OverlayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OverlayError::Usage(e) => write!(f, "{}\n{}", e, crate::args::USAGE),
+            OverlayError::Connect(e) => {
+                write!(f, "Can't connect to the X server ({}), is DISPLAY set?", e)
+            }
+            OverlayError::NoRender => write!(
+                f,
+                "XRender not supported :( and no Shape extension to fall back to"
+            ),
+            OverlayError::NoArgbVisual => write!(
+                f,
+                "Transparency not supported :( and no Shape extension to fall back to"
+            ),
+            OverlayError::NoCompositor => write!(
+                f,
+                "No composite manager running :( and no Shape extension to fall back to"
+            ),
+            OverlayError::NoOutput { name, available } => write!(
+                f,
+                "No output {:?}, available: {}",
+                name,
+                available.join(", ")
+            ),
+            OverlayError::ScreenTooSmall {
+                min_width,
+                min_height,
+            } => write!(
+                f,
+                "Screen too small :(, min size {} x {}",
+                min_width, min_height
+            ),
+            OverlayError::ManifestAuthFailed => write!(f, "Stop patching pls :( (manifest)"),
+            OverlayError::Atlas(e) => write!(f, "Stop patching pls :( ({})", e),
+            OverlayError::InvalidGlyph { index } => {
+                write!(f, "Glyph {} is not in the atlas", index)
+            }
+            OverlayError::MovedGlyph { index } => {
+                write!(f, "Stop patching pls :( (glyph {} moved)", index)
+            }
+            OverlayError::XProtocol(e) => write!(f, "X11 request failed: {}", e),
+            OverlayError::InvalidLayout(problems) => {
+                let problems: Vec<_> = problems.iter().map(|p| p.to_string()).collect();
+                write!(f, "Invalid layout: {}", problems.join(", "))
+            }
+            OverlayError::InvalidStroke { radius, problems } => {
+                let problems: Vec<_> = problems.iter().map(|p| p.to_string()).collect();
+                write!(
+                    f,
+                    "--stroke {} is too much for this layout: {}",
+                    radius,
+                    problems.join(", ")
+                )
+            }
+            OverlayError::TextLayout(e) => {
+                write!(f, "Text doesn't fit: {}, try a smaller --size", e)
+            }
+            OverlayError::MissingGlyph(character) => {
+                write!(f, "The font has no glyph for {:?}", character)
+            }
+            OverlayError::Pack { path, error } => {
+                write!(f, "Can't read pack {}: {}", path.display(), error)
+            }
+            OverlayError::Export { path, error } => {
+                write!(f, "Can't write {}: {}", path.display(), error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OverlayError {}
+
+impl From<ConnectError> for OverlayError {
+    fn from(e: ConnectError) -> Self {
+        OverlayError::Connect(e)
+    }
+}
+
+impl From<NoOutput> for OverlayError {
+    fn from(e: NoOutput) -> Self {
+        OverlayError::NoOutput {
+            name: e.name,
+            available: e.available,
+        }
+    }
+}
+
+impl From<ReplyOrIdError> for OverlayError {
+    fn from(e: ReplyOrIdError) -> Self {
+        OverlayError::XProtocol(e)
+    }
+}
+
+impl From<ReplyError> for OverlayError {
+    fn from(e: ReplyError) -> Self {
+        OverlayError::XProtocol(e.into())
+    }
+}
+
+impl From<ConnectionError> for OverlayError {
+    fn from(e: ConnectionError) -> Self {
+        OverlayError::XProtocol(e.into())
+    }
+}
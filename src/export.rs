@@ -0,0 +1,78 @@
+// Writes the composed 1-bit mask to disk, for looking at the flag without an X server.
+use image::{GrayImage, ImageFormat};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// White glyphs on black, like the glyph assets themselves.
+    Png,
+    /// Binary netpbm bitmap, set bits are glyph pixels.
+    Pbm,
+    /// X bitmap, the same bits the input region is made from.
+    Xbm,
+}
+
+impl Format {
+    /// By file extension.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "png" => Some(Format::Png),
+            "pbm" => Some(Format::Pbm),
+            "xbm" => Some(Format::Xbm),
+            _ => None,
+        }
+    }
+}
+
+/// Write `mask`, every non zero pixel counts as set.
+pub fn write(mask: &GrayImage, format: Format, path: &Path) -> io::Result<()> {
+    match format {
+        Format::Png => mask
+            .save_with_format(path, ImageFormat::Png)
+            .map_err(io::Error::other),
+        Format::Pbm => write_pbm(mask, &mut BufWriter::new(File::create(path)?)),
+        Format::Xbm => write_xbm(mask, &mut BufWriter::new(File::create(path)?)),
+    }
+}
+
+/// Rows of bits, padded to full bytes. `msb_first` decides which end of a byte the leftmost pixel
+/// goes to.
+fn rows(mask: &GrayImage, msb_first: bool) -> Vec<u8> {
+    let stride = mask.width().div_ceil(8) as usize;
+    let mut bytes = vec![0; stride * mask.height() as usize];
+    for (x, y, pixel) in mask.enumerate_pixels() {
+        if pixel.0[0] == 0 {
+            continue;
+        }
+        let bit = if msb_first { 7 - x % 8 } else { x % 8 };
+        bytes[y as usize * stride + x as usize / 8] |= 1 << bit;
+    }
+    bytes
+}
+
+fn write_pbm(mask: &GrayImage, out: &mut impl Write) -> io::Result<()> {
+    write!(out, "P4\n{} {}\n", mask.width(), mask.height())?;
+    out.write_all(&rows(mask, true))?;
+    out.flush()
+}
+
+fn write_xbm(mask: &GrayImage, out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "#define harlem_shake_width {}", mask.width())?;
+    writeln!(out, "#define harlem_shake_height {}", mask.height())?;
+    write!(out, "static unsigned char harlem_shake_bits[] = {{")?;
+    for (i, byte) in rows(mask, false).iter().enumerate() {
+        if i > 0 {
+            write!(out, ",")?;
+        }
+        if i % 12 == 0 {
+            write!(out, "\n  ")?;
+        } else {
+            write!(out, " ")?;
+        }
+        write!(out, "0x{:02x}", byte)?;
+    }
+    writeln!(out, "\n}};")?;
+    out.flush()
+}
@@ -0,0 +1,99 @@
+// Shared between build.rs, which bakes the message's glyphs into the binary, and main.rs, which
+// renders `--text` with the same font at runtime.
+use crate::layout::{Metrics, Typeface};
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use image::{GrayImage, Luma};
+
+pub const FONT_PATH: &str = "fonts/DejaVuSans.ttf";
+pub static FONT: &[u8] = include_bytes!("../fonts/DejaVuSans.ttf");
+
+/// Glyphs of this size stay legible and easy enough to hover on the smallest screen.
+pub const FONT_SIZE: f32 = 130.0;
+/// Sizes `--text` can be drawn at, in layout pixels.
+pub const MIN_FONT_SIZE: f32 = 10.0;
+pub const MAX_FONT_SIZE: f32 = 500.0;
+
+/// The embedded font at a pixel size, `FONT_SIZE` unless asked otherwise.
+pub struct Face {
+    font: FontRef<'static>,
+    scale: PxScale,
+}
+
+impl Face {
+    pub fn new() -> Face {
+        Face::with_size(FONT_SIZE)
+    }
+
+    pub fn with_size(size: f32) -> Face {
+        Face {
+            font: FontRef::try_from_slice(FONT).expect("invalid font"),
+            scale: PxScale::from(size),
+        }
+    }
+
+    /// Whether the font has a glyph for `character`, anything else would come out as a box.
+    pub fn covers(&self, character: char) -> bool {
+        self.font.glyph_id(character).0 != 0
+    }
+
+    /// Render `character`'s ink, sized and positioned as `metrics` says.
+    pub fn rasterize(&self, character: char) -> GrayImage {
+        let metrics = self.metrics(character);
+        let mut img = GrayImage::new(metrics.width as u32, metrics.height as u32);
+        let Some(outline) = self.font.outline_glyph(self.glyph(character)) else {
+            return img;
+        };
+        outline.draw(|x, y, coverage| {
+            // 1-bit, like PIL's mode '1'
+            if coverage >= 0.5 {
+                img.put_pixel(x, y, Luma([255]));
+            }
+        });
+        img
+    }
+
+    /// `character` with the pen on the baseline at 0, 0.
+    fn glyph(&self, character: char) -> ab_glyph::Glyph {
+        let mut glyph = self.font.as_scaled(self.scale).scaled_glyph(character);
+        glyph.position = point(0.0, 0.0);
+        glyph
+    }
+}
+
+impl Default for Face {
+    fn default() -> Face {
+        Face::new()
+    }
+}
+
+impl Typeface for Face {
+    fn metrics(&self, character: char) -> Metrics {
+        let scaled = self.font.as_scaled(self.scale);
+        let advance = scaled.h_advance(scaled.glyph_id(character));
+        match self.font.outline_glyph(self.glyph(character)) {
+            // whole pixels, y grows downwards
+            Some(outline) => {
+                let bounds = outline.px_bounds();
+                Metrics {
+                    width: bounds.width() as u16,
+                    height: bounds.height() as u16,
+                    bearing_x: bounds.min.x as i16,
+                    bearing_y: -bounds.min.y as i16,
+                    advance,
+                }
+            }
+            None => Metrics {
+                width: 0,
+                height: 0,
+                bearing_x: 0,
+                bearing_y: 0,
+                advance,
+            },
+        }
+    }
+
+    fn kern(&self, first: char, second: char) -> f32 {
+        let scaled = self.font.as_scaled(self.scale);
+        scaled.kern(scaled.glyph_id(first), scaled.glyph_id(second))
+    }
+}
@@ -0,0 +1,47 @@
+// `harlem_shake render`: compose the input mask for a made up screen and write it to a file, no X
+// server needed.
+use crate::error::OverlayError;
+use crate::source::Source;
+use harlem_shake::export::{self, Format};
+use harlem_shake::output::Monitor;
+use harlem_shake::stroke::Stroke;
+use image::{GrayImage, Luma};
+use std::path::Path;
+
+/// Same decryption and blitting as `draw`, on a single `width` x `height` monitor.
+pub fn render(
+    source: &Source,
+    width: u16,
+    height: u16,
+    stroke: &Stroke,
+    path: &Path,
+    format: Format,
+) -> Result<(), OverlayError> {
+    let monitors = [Monitor {
+        name: "virtual".to_string(),
+        x: 0,
+        y: 0,
+        width,
+        height,
+        primary: true,
+    }];
+
+    let mut img = crate::mask_image(width, height);
+    for index in 0..source.len() {
+        crate::put_char(index, source, &monitors, stroke, &mut img)?;
+    }
+
+    let mask = GrayImage::from_fn(width as u32, height as u32, |x, y| {
+        Luma([if img.get_pixel(x as u16, y as u16) != 0 {
+            255
+        } else {
+            0
+        }])
+    });
+    export::write(&mask, format, path).map_err(|error| OverlayError::Export {
+        path: path.to_path_buf(),
+        error,
+    })?;
+    println!("Wrote {} x {} mask to {}", width, height, path.display());
+    Ok(())
+}
@@ -0,0 +1,396 @@
+// Shared between build.rs, which lays the message out, and main.rs, which draws it.
+//
+// Text is typeset like a font renderer would: every glyph is a bitmap of just its ink, placed by
+// the font's bearings relative to a pen that moves on by the glyph's advance, plus kerning.
+use image::GrayImage;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::fmt;
+use std::str::FromStr;
+
+/// Screen size the layout is computed for, the smallest screen the overlay runs on.
+pub const SCREEN_WIDTH: u16 = 1900;
+pub const SCREEN_HEIGHT: u16 = 900;
+
+/// Space kept free left and right of every line.
+pub const MARGIN: u16 = 100;
+/// Space kept free above the first line and below the last, jitter included. Less than `MARGIN`,
+/// or long messages wouldn't fit in five lines any more.
+pub const VERTICAL_MARGIN: u16 = 50;
+
+/// Where a glyph's bitmap goes, its top left corner.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Origin {
+    pub x: i16,
+    pub y: i16,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Placement {
+    pub character: char,
+    pub origin: Origin,
+}
+
+/// How a glyph's bitmap sits relative to the pen, in layout pixels.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Metrics {
+    /// Size of the bitmap, 0 x 0 for glyphs without ink like space.
+    pub width: u16,
+    pub height: u16,
+    /// From the pen to the bitmap's left edge.
+    pub bearing_x: i16,
+    /// From the baseline up to the bitmap's top edge.
+    pub bearing_y: i16,
+    /// How far the pen moves on after the glyph.
+    pub advance: f32,
+}
+
+/// What the layout needs to know about a font.
+pub trait Typeface {
+    fn metrics(&self, character: char) -> Metrics;
+    /// Added to the advance between `first` and `second`, usually negative.
+    fn kern(&self, first: char, second: char) -> f32;
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// Everything on one line, centered.
+    SingleLine,
+    /// Lines broken after `_` or spaces where possible, spread over the screen.
+    Wrapped,
+    /// Like `Wrapped`, but with random line breaks, lines shifted sideways and drawn in random
+    /// order. The screen still reads as the message, only the order of the manifest gives
+    /// nothing away.
+    Scrambled,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Options {
+    pub strategy: Strategy,
+    /// Drives everything random, the same seed and message always give the same placements.
+    pub seed: u64,
+    /// Glyphs are moved up or down by up to this many pixels. Only vertically, moving them
+    /// sideways would let neighbours overlap. Capped at half the gap between lines, so lines
+    /// don't run into each other either.
+    pub jitter: u16,
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "single-line" => Ok(Strategy::SingleLine),
+            "wrapped" => Ok(Strategy::Wrapped),
+            "scrambled" => Ok(Strategy::Scrambled),
+            _ => Err(format!(
+                "unknown layout {:?}, expected single-line, wrapped or scrambled",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LayoutError {
+    /// A line, or a single glyph, is wider than the screen between the margins.
+    TooWide { width: u32, max: u32 },
+    /// The lines don't fit on the screen.
+    TooTall { lines: usize, max: usize },
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LayoutError::TooWide { width, max } => write!(
+                f,
+                "message needs {}px wide lines, only {}px fit on screen",
+                width, max
+            ),
+            LayoutError::TooTall { lines, max } => write!(
+                f,
+                "message needs {} lines, only {} fit on screen",
+                lines, max
+            ),
+        }
+    }
+}
+
+/// Something wrong with where glyphs are placed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Problem {
+    /// The glyph's bitmap reaches past the edge of the screen.
+    OffScreen { index: usize, origin: Origin },
+    /// On screen, but closer than `MARGIN` to its left or right edge, or than `VERTICAL_MARGIN`
+    /// to its top or bottom.
+    InMargin { index: usize, origin: Origin },
+    /// Two glyphs have ink on the same pixel.
+    Overlap { first: usize, second: usize },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::OffScreen { index, origin } => write!(
+                f,
+                "glyph {} at {}, {} is off screen",
+                index, origin.x, origin.y
+            ),
+            Problem::InMargin { index, origin } => write!(
+                f,
+                "glyph {} at {}, {} is in the margin",
+                index, origin.x, origin.y
+            ),
+            Problem::Overlap { first, second } => {
+                write!(f, "glyphs {} and {} overlap", first, second)
+            }
+        }
+    }
+}
+
+/// Check glyph bitmaps at their origins against a `width` x `height` screen. Kerning tucks
+/// glyphs into each other's bounding boxes, so only ink on the same pixel counts as overlap.
+/// Whatever `layout` places there passes.
+pub fn validate(glyphs: &[(Origin, &GrayImage)], width: u16, height: u16) -> Vec<Problem> {
+    let bounds = |(origin, glyph): &(Origin, &GrayImage)| {
+        let (x, y) = (origin.x as i32, origin.y as i32);
+        (x, y, x + glyph.width() as i32, y + glyph.height() as i32)
+    };
+
+    let mut problems = vec![];
+    for (index, glyph) in glyphs.iter().enumerate() {
+        let origin = glyph.0;
+        let (left, top, right, bottom) = bounds(glyph);
+        if left < 0 || top < 0 || right > width as i32 || bottom > height as i32 {
+            problems.push(Problem::OffScreen { index, origin });
+        } else if left < MARGIN as i32
+            || right > width as i32 - MARGIN as i32
+            || top < VERTICAL_MARGIN as i32
+            || bottom > height as i32 - VERTICAL_MARGIN as i32
+        {
+            problems.push(Problem::InMargin { index, origin });
+        }
+    }
+    for (first, a) in glyphs.iter().enumerate() {
+        for (second, b) in glyphs.iter().enumerate().skip(first + 1) {
+            let ((a_left, a_top, a_right, a_bottom), (b_left, b_top, b_right, b_bottom)) =
+                (bounds(a), bounds(b));
+            let overlap = (a_left.max(b_left)..a_right.min(b_right)).any(|x| {
+                (a_top.max(b_top)..a_bottom.min(b_bottom)).any(|y| {
+                    let ink = |(origin, glyph): &(Origin, &GrayImage)| {
+                        let (x, y) = (x - origin.x as i32, y - origin.y as i32);
+                        glyph.get_pixel(x as u32, y as u32).0[0] != 0
+                    };
+                    ink(a) && ink(b)
+                })
+            });
+            if overlap {
+                problems.push(Problem::Overlap { first, second });
+            }
+        }
+    }
+    problems
+}
+
+/// Split after every `_` and space, the places a line may be broken.
+fn words(message: &str) -> Vec<Vec<char>> {
+    let mut words = vec![vec![]];
+    for character in message.chars() {
+        words.last_mut().unwrap().push(character);
+        if character == '_' || character == ' ' {
+            words.push(vec![]);
+        }
+    }
+    words.retain(|word| !word.is_empty());
+    words
+}
+
+/// Pen position of every character of `line`, from the line's start.
+fn pens(line: &[char], typeface: &impl Typeface) -> Vec<f32> {
+    let mut pen = 0.0;
+    let mut pens = Vec::with_capacity(line.len());
+    for (i, &character) in line.iter().enumerate() {
+        pens.push(pen);
+        pen += typeface.metrics(character).advance;
+        if let Some(&next) = line.get(i + 1) {
+            pen += typeface.kern(character, next);
+        }
+    }
+    pens
+}
+
+/// Leftmost and rightmost inked pixel of `line`, relative to its start. Spaces don't count, so
+/// lines are centered by what can be seen.
+fn extent(line: &[char], typeface: &impl Typeface) -> (f32, f32) {
+    let mut extent: Option<(f32, f32)> = None;
+    for (&character, pen) in line.iter().zip(pens(line, typeface)) {
+        let metrics = typeface.metrics(character);
+        if metrics.width == 0 {
+            continue;
+        }
+        let left = (pen + metrics.bearing_x as f32).round();
+        let right = left + metrics.width as f32;
+        extent = Some(match extent {
+            Some((min, max)) => (min.min(left), max.max(right)),
+            None => (left, right),
+        });
+    }
+    extent.unwrap_or((0.0, 0.0))
+}
+
+fn line_width(line: &[char], typeface: &impl Typeface) -> u32 {
+    let (left, right) = extent(line, typeface);
+    (right - left) as u32
+}
+
+/// Break the message into lines at most `max` pixels wide.
+fn wrap(message: &str, max: u32, typeface: &impl Typeface) -> Result<Vec<Vec<char>>, LayoutError> {
+    let fits = |line: &[char]| line_width(line, typeface) <= max;
+    let mut lines = vec![];
+    let mut line: Vec<char> = vec![];
+    for word in words(message) {
+        if fits(&[&line[..], &word[..]].concat()) {
+            line.extend(word);
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        // words longer than a whole line get broken anywhere
+        for character in word {
+            if !fits(&[&line[..], &[character]].concat()) {
+                if line.is_empty() {
+                    return Err(LayoutError::TooWide {
+                        width: line_width(&[character], typeface),
+                        max,
+                    });
+                }
+                lines.push(std::mem::take(&mut line));
+            }
+            line.push(character);
+        }
+    }
+    lines.push(line);
+
+    // trailing spaces would only push the next line's words around
+    for line in &mut lines {
+        while line.last() == Some(&' ') {
+            line.pop();
+        }
+    }
+    Ok(lines)
+}
+
+/// Typeset every character of `message` on a `width` x `height` screen.
+pub fn layout(
+    message: &str,
+    width: u16,
+    height: u16,
+    typeface: &impl Typeface,
+    options: &Options,
+) -> Result<Vec<Placement>, LayoutError> {
+    let mut rng = ChaCha8Rng::seed_from_u64(options.seed);
+    let max_width = width.saturating_sub(2 * MARGIN) as u32;
+    let max_height = height.saturating_sub(2 * VERTICAL_MARGIN);
+
+    // every line gets the same height, from the highest to the lowest reaching glyph
+    let (ascent, descent) = message
+        .chars()
+        .map(|c| typeface.metrics(c))
+        .filter(|m| m.height > 0)
+        .fold((0, 0), |(ascent, descent), m| {
+            (
+                ascent.max(m.bearing_y as i32),
+                descent.max(m.height as i32 - m.bearing_y as i32),
+            )
+        });
+    let line_height = (ascent + descent).max(1) as u16;
+    let max_lines = (max_height / line_height) as usize;
+
+    let lines = match options.strategy {
+        Strategy::SingleLine => {
+            let line: Vec<char> = message.chars().collect();
+            let line_width = line_width(&line, typeface);
+            if line_width > max_width {
+                return Err(LayoutError::TooWide {
+                    width: line_width,
+                    max: max_width,
+                });
+            }
+            vec![line]
+        }
+        Strategy::Wrapped | Strategy::Scrambled => {
+            // scrambled layouts break lines early at random, unless that no longer fits
+            let lines = match options.strategy {
+                Strategy::Scrambled => wrap(
+                    message,
+                    rng.gen_range(max_width.div_ceil(2)..=max_width.max(1)),
+                    typeface,
+                ),
+                _ => wrap(message, max_width, typeface),
+            };
+            match lines {
+                Ok(lines) if lines.len() <= max_lines => lines,
+                _ => wrap(message, max_width, typeface)?,
+            }
+        }
+    };
+    if lines.len() > max_lines {
+        return Err(LayoutError::TooTall {
+            lines: lines.len(),
+            max: max_lines,
+        });
+    }
+
+    // spread the lines evenly from the top margin to the bottom one
+    let gap = (max_height - lines.len() as u16 * line_height) / (lines.len() as u16 + 1);
+    let jitter = options.jitter.min(gap / 2) as i16;
+
+    let mut placements = vec![];
+    for (row, line) in lines.iter().enumerate() {
+        let baseline = (VERTICAL_MARGIN + gap + row as u16 * (line_height + gap)) as i32 + ascent;
+        let (left, right) = extent(line, typeface);
+        let line_width = (right - left) as u32;
+        // where the line's ink starts
+        let start = match options.strategy {
+            Strategy::Scrambled => {
+                rng.gen_range(MARGIN as u32..=width as u32 - MARGIN as u32 - line_width)
+            }
+            _ => (width as u32 - line_width) / 2,
+        } as f32
+            - left;
+        // glyphs reaching into each other's columns, kerned or not, move up and down together,
+        // or they could collide
+        let (mut reach, mut dy) = (i16::MIN, 0);
+        for (&character, pen) in line.iter().zip(pens(line, typeface)) {
+            let jittered = if jitter > 0 {
+                rng.gen_range(-jitter..=jitter)
+            } else {
+                0
+            };
+            let metrics = typeface.metrics(character);
+            // nothing to draw, but it still moves the pen
+            if metrics.width == 0 || metrics.height == 0 {
+                continue;
+            }
+            let x = (start + pen + metrics.bearing_x as f32).round() as i16;
+            if x >= reach {
+                dy = jittered;
+            }
+            reach = reach.max(x + metrics.width as i16);
+            placements.push(Placement {
+                character,
+                origin: Origin {
+                    x,
+                    y: (baseline - metrics.bearing_y as i32) as i16 + dy,
+                },
+            });
+        }
+    }
+
+    if options.strategy == Strategy::Scrambled {
+        placements.shuffle(&mut rng);
+    }
+    Ok(placements)
+}
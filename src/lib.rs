@@ -0,0 +1,13 @@
+//! Everything that works without an X connection. build.rs pulls the same files in with
+//! `#[path]`, so the assets are always written the way the overlay reads them.
+pub mod atlas;
+pub mod blit;
+pub mod crypto;
+pub mod export;
+pub mod font;
+pub mod layout;
+pub mod manifest;
+pub mod output;
+pub mod pack;
+pub mod scale;
+pub mod stroke;
@@ -1,10 +1,12 @@
 use x11rb::connection::Connection;
-use x11rb::errors::ReplyError;
+use x11rb::errors::{ConnectionError, ReplyError, ReplyOrIdError};
 use x11rb::image::{BitsPerPixel, Image, ImageOrder, ScanlinePad};
+use x11rb::protocol::randr;
 use x11rb::protocol::render::{ConnectionExt as _, PictType};
-use x11rb::protocol::shape::{self};
+use x11rb::protocol::shape;
 use x11rb::protocol::xfixes::{ConnectionExt as _, RegionWrapper};
 use x11rb::protocol::xproto::{ConnectionExt as _, *};
+use x11rb::protocol::Event;
 use x11rb::reexports::x11rb_protocol::protocol::render;
 use x11rb::rust_connection::RustConnection;
 use x11rb::wrapper::ConnectionExt;
@@ -16,16 +18,53 @@ use std::thread::{sleep, spawn};
 use std::time::Duration;
 
 // use image::GenericImageView;
-use image::ImageFormat;
-use image::ImageReader;
+use image::GrayImage;
 #[cfg(not(debug_assertions))]
 use rand::Rng;
-use std::io::Cursor;
+use std::process::ExitCode;
+
+use harlem_shake::blit;
+use harlem_shake::font::FONT_SIZE;
+use harlem_shake::layout::Origin;
+use harlem_shake::stroke::Stroke;
+
+mod args;
+mod error;
+mod headless;
+mod monitors;
+mod shm;
+mod source;
+mod trace;
+use args::{Args, Command};
+use error::OverlayError;
+use harlem_shake::output::{self, Monitor, Target};
+use source::Source;
+use trace::Traced;
+
+/// Logs every request with `--trace`.
+type Conn = Traced<RustConnection>;
+
+/// How the window keeps everything but the glyphs visible.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Mode {
+    /// ARGB window, the compositor blends it over the desktop.
+    Composited,
+    /// Without a compositor or ARGB visual: an input-only window, nothing is drawn and only its
+    /// input shape is set to the glyph mask, so nothing on screen changes.
+    InputOnly,
+}
 
-use chacha20poly1305::{
-    aead::{Aead, KeyInit},
-    ChaCha20Poly1305, Key, Nonce,
-};
+/// The window everything is drawn into.
+#[derive(Copy, Clone, Debug)]
+struct Overlay {
+    window: u32,
+    depth: u8,
+    mode: Mode,
+    /// Upload images through MIT-SHM instead of the socket.
+    shm: bool,
+    /// How thick the glyphs in the mask are.
+    stroke: Stroke,
+}
 
 x11rb::atom_manager! {
     pub Atoms: AtomCollectionCookie {
@@ -44,48 +83,47 @@ x11rb::atom_manager! {
     }
 }
 
-/// Choose a visual to use. This function tries to find a depth=32 visual and falls back to the
-/// screen's default visual.
+/// Find the depth=32 ARGB visual, `None` on 24-bit only displays. Without XRender there is no
+/// way to tell, so that's an error.
 fn choose_visual(
     conn: &impl Connection,
-    screen: &Screen,
     screen_num: usize,
-) -> Result<(u8, Visualid), ReplyError> {
+) -> Result<Option<Visualid>, OverlayError> {
     let depth = 32;
 
     // Try to use XRender to find a visual with alpha support
     let has_render = conn
-        .extension_information(render::X11_EXTENSION_NAME)
-        .unwrap()
+        .extension_information(render::X11_EXTENSION_NAME)?
         .is_some();
-    if has_render {
-        let formats = conn.render_query_pict_formats().unwrap().reply().unwrap();
-        // Find the ARGB32 format that must be supported.
-        let format = formats
-            .formats
+    if !has_render {
+        return Err(OverlayError::NoRender);
+    }
+    let formats = conn.render_query_pict_formats()?.reply()?;
+    // Find the ARGB32 format that must be supported.
+    let format = formats
+        .formats
+        .iter()
+        .filter(|info| (info.type_, info.depth) == (PictType::DIRECT, depth))
+        .filter(|info| {
+            let d = info.direct;
+            (d.red_mask, d.green_mask, d.blue_mask, d.alpha_mask) == (0xff, 0xff, 0xff, 0xff)
+        })
+        .find(|info| {
+            let d = info.direct;
+            (d.red_shift, d.green_shift, d.blue_shift, d.alpha_shift) == (16, 8, 0, 24)
+        });
+    if let Some(format) = format {
+        // Now we need to find the visual that corresponds to this format
+        if let Some(visual) = formats.screens[screen_num]
+            .depths
             .iter()
-            .filter(|info| (info.type_, info.depth) == (PictType::DIRECT, depth))
-            .filter(|info| {
-                let d = info.direct;
-                (d.red_mask, d.green_mask, d.blue_mask, d.alpha_mask) == (0xff, 0xff, 0xff, 0xff)
-            })
-            .find(|info| {
-                let d = info.direct;
-                (d.red_shift, d.green_shift, d.blue_shift, d.alpha_shift) == (16, 8, 0, 24)
-            });
-        if let Some(format) = format {
-            // Now we need to find the visual that corresponds to this format
-            if let Some(visual) = formats.screens[screen_num]
-                .depths
-                .iter()
-                .flat_map(|d| &d.visuals)
-                .find(|v| v.format == format.id)
-            {
-                return Ok((format.depth, visual.visual));
-            }
+            .flat_map(|d| &d.visuals)
+            .find(|v| v.format == format.id)
+        {
+            return Ok(Some(visual.visual));
         }
     }
-    Ok((screen.root_depth, screen.root_visual))
+    Ok(None)
 }
 
 fn composite_manager_running(
@@ -93,44 +131,80 @@ fn composite_manager_running(
     screen_num: usize,
 ) -> Result<bool, ReplyError> {
     let atom = format!("_NET_WM_CM_S{}", screen_num);
-    let atom = conn
-        .intern_atom(false, atom.as_bytes())
-        .unwrap()
-        .reply()
-        .unwrap()
-        .atom;
-    let owner = conn.get_selection_owner(atom).unwrap().reply()?;
+    let atom = conn.intern_atom(false, atom.as_bytes())?.reply()?.atom;
+    let owner = conn.get_selection_owner(atom)?.reply()?;
     Ok(owner.owner != x11rb::NONE)
 }
 
-#[derive(Copy, Clone)]
-struct Origin {
-    x: i16,
-    y: i16,
+fn shape_supported(conn: &impl Connection) -> Result<bool, ConnectionError> {
+    Ok(conn
+        .extension_information(shape::X11_EXTENSION_NAME)?
+        .is_some())
+}
+
+/// Composited if the server can do it, input-only otherwise. Tells the user which one and why.
+fn choose_mode(
+    conn: &impl Connection,
+    screen_num: usize,
+) -> Result<(Mode, u8, Visualid), OverlayError> {
+    let (error, reason) = if !composite_manager_running(conn, screen_num)? {
+        (OverlayError::NoCompositor, "No composite manager running")
+    } else {
+        // check if we support alpha channel
+        match choose_visual(conn, screen_num) {
+            Ok(Some(visual_id)) => {
+                println!("Running composited");
+                return Ok((Mode::Composited, 32, visual_id));
+            }
+            // Does not support alpha channel, e.g. a 24-bit only display. The root visual
+            // would draw the glyphs opaque, so it's input-only there too
+            Ok(None) => (OverlayError::NoArgbVisual, "No 32-bit ARGB visual"),
+            Err(OverlayError::NoRender) => (OverlayError::NoRender, "No XRender"),
+            Err(e) => return Err(e),
+        }
+    };
+
+    if !shape_supported(conn)? {
+        return Err(error);
+    }
+    println!("{}, falling back to an input-only window", reason);
+    Ok((Mode::InputOnly, 0, x11rb::COPY_FROM_PARENT))
 }
 
 fn create_window(
-    conn: Arc<RustConnection>,
+    conn: Arc<Conn>,
     screen: &Screen,
     visual_id: Visualid,
     atoms: Atoms,
-    window: u32,
-    depth: u8,
-) {
-    let colormap =
-        ColormapWrapper::create_colormap(&conn, ColormapAlloc::NONE, screen.root, visual_id)
-            .unwrap();
+    overlay: &Overlay,
+) -> Result<(), ReplyOrIdError> {
+    let _scope = trace::scope("create_window");
+    let window = overlay.window;
     let win_aux = CreateWindowAux::new()
         .event_mask(EventMask::NO_EVENT)
-        .background_pixel(x11rb::NONE)
-        .border_pixel(x11rb::NONE)
         // important to be treated as "popup" window, which we want
         // https://tronche.com/gui/x/xlib/window/attributes/override-redirect.html
-        .override_redirect(1)
-        .colormap(colormap.colormap());
+        .override_redirect(1);
+    // an input-only window has no pixels, so no background, border or colormap either
+    let (class, win_aux, _colormap) = match overlay.mode {
+        Mode::Composited => {
+            let colormap = ColormapWrapper::create_colormap(
+                &conn,
+                ColormapAlloc::NONE,
+                screen.root,
+                visual_id,
+            )?;
+            let win_aux = win_aux
+                .background_pixel(x11rb::NONE)
+                .border_pixel(x11rb::NONE)
+                .colormap(colormap.colormap());
+            (WindowClass::INPUT_OUTPUT, win_aux, Some(colormap))
+        }
+        Mode::InputOnly => (WindowClass::INPUT_ONLY, win_aux, None),
+    };
 
     conn.create_window(
-        depth,
+        overlay.depth,
         window,
         screen.root,
         0,
@@ -138,11 +212,10 @@ fn create_window(
         screen.width_in_pixels,
         screen.height_in_pixels,
         0,
-        WindowClass::INPUT_OUTPUT,
+        class,
         visual_id,
         &win_aux,
-    )
-    .unwrap();
+    )?;
 
     conn.change_property32(
         PropMode::REPLACE,
@@ -156,8 +229,7 @@ fn create_window(
             atoms._NET_WM_STATE_SKIP_TASKBAR,
             atoms._NET_WM_STATE_SKIP_PAGER,
         ],
-    )
-    .unwrap();
+    )?;
 
     // set global window opacity. may not be needed
     conn.change_property32(
@@ -166,8 +238,7 @@ fn create_window(
         atoms._NET_WM_WINDOW_OPACITY,
         AtomEnum::CARDINAL,
         &[0x20cccccc],
-    )
-    .unwrap();
+    )?;
 
     // TODO:
     // https://github.com/Plagman/gamescope/issues/288
@@ -180,603 +251,377 @@ fn create_window(
         atoms.GAMESCOPE_EXTERNAL_OVERLAY,
         AtomEnum::CARDINAL,
         &[1],
-    )
-    .unwrap();
+    )?;
 
-    conn.map_window(window).unwrap();
-    conn.flush().unwrap();
+    conn.map_window(window)?;
+    conn.flush()?;
+    Ok(())
 }
 
-fn create_region(conn: Arc<RustConnection>, window: u32, pixmap: Pixmap) {
-    let region = RegionWrapper::create_region_from_bitmap(&conn, pixmap).unwrap();
+fn create_region(conn: Arc<Conn>, overlay: &Overlay, pixmap: Pixmap) -> Result<(), ReplyOrIdError> {
+    let _scope = trace::scope("create_region");
+    let region = RegionWrapper::create_region_from_bitmap(&conn, pixmap)?;
 
-    conn.xfixes_set_window_shape_region(window, shape::SK::INPUT, 0, 0, region.region())
-        .unwrap();
-    conn.flush().unwrap();
+    conn.xfixes_set_window_shape_region(overlay.window, shape::SK::INPUT, 0, 0, region.region())?;
+    conn.flush()?;
+    Ok(())
 }
 
 #[cfg(debug_assertions)]
-const CHAR_HEIGHT: u16 = 170;
-#[cfg(debug_assertions)]
-const CHAR_WIDTH: u16 = 100;
-
-#[cfg(debug_assertions)]
-fn draw_letter(conn: Arc<RustConnection>, origin: Origin, letter: Vec<u8>, window: u32) {
-    let reader = ImageReader::with_format(Cursor::new(letter), ImageFormat::Png);
-    // sadly there is no 1-bit-png format in the image-crate
-    let image = reader.decode().unwrap().into_luma8();
-
-    assert!(image.width() as u16 == CHAR_WIDTH);
-    assert!(image.height() as u16 == CHAR_HEIGHT);
+fn draw_letter(
+    conn: Arc<Conn>,
+    origin: Origin,
+    image: &GrayImage,
+    overlay: &Overlay,
+) -> Result<(), ReplyOrIdError> {
+    let _scope = trace::scope("draw_letter");
+    let (width, height) = (image.width() as u16, image.height() as u16);
 
     let mut img = Image::allocate(
-        CHAR_WIDTH,
-        CHAR_HEIGHT,
+        width,
+        height,
         ScanlinePad::Pad32,
-        32,
+        overlay.depth,
         BitsPerPixel::B32,
         ImageOrder::MsbFirst,
     );
-    for (x, y, pixel) in image.enumerate_pixels() {
-        img.put_pixel(x as u16, y as u16, 0x88000000 | (pixel.0[0] as u32 * 255));
-    }
+    let clip = blit::Clip::of(&img);
+    // black and white already, put_char applied the stroke
+    blit::argb(
+        &mut img,
+        image,
+        0,
+        0,
+        clip,
+        u8::MAX,
+        0x88000000 | (255 * 255),
+        0x88000000,
+    );
 
     let gc = GcontextWrapper::create_gc(
         &conn,
-        window,
+        overlay.window,
         &CreateGCAux::new().graphics_exposures(0).foreground(1), //screen.white_pixel),
-    )
-    .unwrap();
+    )?;
 
-    let pixmap = PixmapWrapper::create_pixmap(&conn, 32, window, CHAR_WIDTH, CHAR_HEIGHT).unwrap();
-    img.put(&conn, pixmap.pixmap(), gc.gcontext(), 0, 0)
-        .unwrap();
+    let pixmap = PixmapWrapper::create_pixmap(&conn, overlay.depth, overlay.window, width, height)?;
+    shm::put_image(&conn, overlay.shm, &img, pixmap.pixmap(), gc.gcontext())?;
 
     // idk why I have to copy here, but I have to :/
     conn.copy_area(
         pixmap.pixmap(),
-        window,
+        overlay.window,
         gc.gcontext(),
         0,
         0,
         origin.x,
         origin.y,
-        CHAR_WIDTH,
-        CHAR_HEIGHT,
+        width,
+        height,
+    )?;
+    conn.flush()?;
+    Ok(())
+}
+
+// generated by build.rs, fresh for every build unless HARLEM_SHAKE_SEED is set
+static KEY: &[u8; 32] = include_bytes!(concat!(env!("OUT_DIR"), "/key.bin"));
+
+// encrypted list of where to draw which character from ATLAS
+static MANIFEST: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/manifest.bin"));
+static ATLAS: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/atlas.bin"));
+
+/// Empty 1-bit image for the glyphs to go into, in the layout CreateRegionFromBitmap wants.
+fn mask_image(width: u16, height: u16) -> Image<'static> {
+    Image::allocate(
+        width,
+        height,
+        ScanlinePad::Pad8,
+        1,
+        BitsPerPixel::B1,
+        ImageOrder::MsbFirst,
     )
-    .unwrap();
-    conn.flush().unwrap();
 }
 
+/// Get glyph `index` from `source` and set its pixels in `img`, fitted to the area of all
+/// `monitors` and with `stroke` applied. Returns where the final glyph went.
 fn put_char(
-    conn: Arc<RustConnection>,
-    pos: Origin,
-    encrypted_letter: &[u8],
+    index: usize,
+    source: &Source,
+    monitors: &[Monitor],
+    stroke: &Stroke,
     img: &mut Image,
-    window: u32,
-) {
-    let key = Key::from_slice(&[
-        145, 177, 108, 160, 218, 93, 51, 44, 185, 144, 149, 150, 190, 95, 105, 24, 240, 225, 25,
-        86, 245, 86, 133, 241, 17, 209, 5, 196, 165, 236, 95, 88,
-    ]);
-    let cipher = ChaCha20Poly1305::new(key);
-
-    let (read_nonce, encrypted_letter_plain) = encrypted_letter.split_at(12);
-
-    let nonce = Nonce::from_slice(read_nonce);
-
-    // letter has to consist of encrypted bytes + tag
-    let letter = cipher
-        .decrypt(nonce, encrypted_letter_plain.as_ref())
-        .expect("Stop patching pls :(");
-
-    let reader = ImageReader::with_format(Cursor::new(letter.clone()), ImageFormat::Png);
-    // sadly there is no 1-bit-png format in the image-crate
-    let image = reader.decode().unwrap().into_luma8();
-
-    #[cfg(debug_assertions)]
-    assert!(image.width() as u16 == CHAR_WIDTH);
-    #[cfg(debug_assertions)]
-    assert!(image.height() as u16 == CHAR_HEIGHT);
-
-    for (x, y, pixel) in image.enumerate_pixels() {
-        img.put_pixel(
-            pos.x as u16 + x as u16,
-            pos.y as u16 + y as u16,
-            pixel.0[0] as u32,
-        );
+) -> Result<(Origin, GrayImage), OverlayError> {
+    let (pos, image) = source.glyph(index)?;
+
+    // the layout is made for SCREEN_WIDTH x SCREEN_HEIGHT, fit it to the monitors
+    let transform = output::area(monitors).transform();
+    let (image, border) = stroke.apply(&transform.glyph(&image), transform.scale);
+    let pos = transform.origin(pos);
+    // a dilated glyph grew on every side
+    let pos = Origin {
+        x: pos.x - border as i16,
+        y: pos.y - border as i16,
+    };
+
+    // the input region must not reach into other monitors or the gap between them
+    let monitor = output::shown_on(
+        monitors,
+        pos.x as i32 + image.width() as i32 / 2,
+        pos.y as i32 + image.height() as i32 / 2,
+    );
+    blit::mask(
+        img,
+        &image,
+        pos.x as i32,
+        pos.y as i32,
+        monitor.clip(),
+        u8::MAX,
+    );
+    Ok((pos, image))
+}
+
+/// The monitors of `target` to draw on.
+fn select_monitors(
+    conn: &impl Connection,
+    screen: &Screen,
+    target: &Target,
+    args: &Args,
+) -> Result<Vec<Monitor>, OverlayError> {
+    let monitors = monitors::monitors(conn, screen)?;
+    let monitors = output::select(monitors, target)?;
+
+    // min screen size
+    if monitors
+        .iter()
+        .any(|m| m.width < args.min_width || m.height < args.min_height)
+    {
+        return Err(OverlayError::ScreenTooSmall {
+            min_width: args.min_width,
+            min_height: args.min_height,
+        });
     }
+    Ok(monitors)
+}
 
-    // debug drawing
-    #[cfg(debug_assertions)]
-    draw_letter(conn, pos, letter, window);
+/// The monitors to draw on after the screen changed. An unplugged output or a screen that got
+/// too small doesn't end the overlay: it moves to the primary output, or stays on `last`.
+fn reselect_monitors(
+    conn: &impl Connection,
+    screen: &Screen,
+    args: &Args,
+    last: &[Monitor],
+) -> Result<Vec<Monitor>, OverlayError> {
+    let error = match select_monitors(conn, screen, &args.target, args) {
+        Ok(monitors) => return Ok(monitors),
+        Err(e @ (OverlayError::NoOutput { .. } | OverlayError::ScreenTooSmall { .. })) => e,
+        Err(e) => return Err(e),
+    };
+    if let OverlayError::NoOutput { .. } = error {
+        if let Ok(monitors) = select_monitors(conn, screen, &Target::Primary, args) {
+            println!("Warning: {}, moving to the primary output", error);
+            return Ok(monitors);
+        }
+    }
+    println!("Warning: {}, keeping the last layout", error);
+    Ok(last.to_vec())
 }
 
-#[allow(non_snake_case)]
-fn main() {
-    let _letter_a = include_bytes!("../letters/a.png");
-    let _letter_b = include_bytes!("../letters/b.png");
-    let _letter_c = include_bytes!("../letters/c.png");
-    let _letter_d = include_bytes!("../letters/d.png");
-    let _letter_e = include_bytes!("../letters/e.png");
-    let _letter_f = include_bytes!("../letters/f.png");
-    let _letter_g = include_bytes!("../letters/g.png");
-    let _letter_h = include_bytes!("../letters/h.png");
-    let _letter_i = include_bytes!("../letters/i.png");
-    let _letter_j = include_bytes!("../letters/j.png");
-    let _letter_k = include_bytes!("../letters/k.png");
-    let _letter_l = include_bytes!("../letters/l.png");
-    let _letter_m = include_bytes!("../letters/m.png");
-    let _letter_n = include_bytes!("../letters/n.png");
-    let _letter_o = include_bytes!("../letters/o.png");
-    let _letter_p = include_bytes!("../letters/p.png");
-    let _letter_q = include_bytes!("../letters/q.png");
-    let _letter_r = include_bytes!("../letters/r.png");
-    let _letter_s = include_bytes!("../letters/s.png");
-    let _letter_t = include_bytes!("../letters/t.png");
-    let _letter_u = include_bytes!("../letters/u.png");
-    let _letter_v = include_bytes!("../letters/v.png");
-    let _letter_w = include_bytes!("../letters/w.png");
-    let _letter_x = include_bytes!("../letters/x.png");
-    let _letter_y = include_bytes!("../letters/y.png");
-    let _letter_z = include_bytes!("../letters/z.png");
-    let _letter_A = include_bytes!("../letters/A.png");
-    let _letter_B = include_bytes!("../letters/B.png");
-    let _letter_C = include_bytes!("../letters/C.png");
-    let _letter_D = include_bytes!("../letters/D.png");
-    let _letter_E = include_bytes!("../letters/E.png");
-    let _letter_F = include_bytes!("../letters/F.png");
-    let _letter_G = include_bytes!("../letters/G.png");
-    let _letter_H = include_bytes!("../letters/H.png");
-    let _letter_I = include_bytes!("../letters/I.png");
-    let _letter_J = include_bytes!("../letters/J.png");
-    let _letter_K = include_bytes!("../letters/K.png");
-    let _letter_L = include_bytes!("../letters/L.png");
-    let _letter_M = include_bytes!("../letters/M.png");
-    let _letter_N = include_bytes!("../letters/N.png");
-    let _letter_O = include_bytes!("../letters/O.png");
-    let _letter_P = include_bytes!("../letters/P.png");
-    let _letter_Q = include_bytes!("../letters/Q.png");
-    let _letter_R = include_bytes!("../letters/R.png");
-    let _letter_S = include_bytes!("../letters/S.png");
-    let _letter_T = include_bytes!("../letters/T.png");
-    let _letter_U = include_bytes!("../letters/U.png");
-    let _letter_V = include_bytes!("../letters/V.png");
-    let _letter_W = include_bytes!("../letters/W.png");
-    let _letter_X = include_bytes!("../letters/X.png");
-    let _letter_Y = include_bytes!("../letters/Y.png");
-    let _letter_Z = include_bytes!("../letters/Z.png");
-    let _letter_0 = include_bytes!("../letters/0.png");
-    let _letter_1 = include_bytes!("../letters/1.png");
-    let _letter_2 = include_bytes!("../letters/2.png");
-    let _letter_3 = include_bytes!("../letters/3.png");
-    let _letter_4 = include_bytes!("../letters/4.png");
-    let _letter_5 = include_bytes!("../letters/5.png");
-    let _letter_6 = include_bytes!("../letters/6.png");
-    let _letter_7 = include_bytes!("../letters/7.png");
-    let _letter_8 = include_bytes!("../letters/8.png");
-    let _letter_9 = include_bytes!("../letters/9.png");
-    let _letter__ = include_bytes!("../letters/_.png");
-    let _letter_open = include_bytes!("../letters/{.png");
-    let _letter_close = include_bytes!("../letters/}.png");
-    let _letter_quote = include_bytes!("../letters/'.png");
-
-    let _letters: Vec<&[u8]> = vec![
-        _letter_a,
-        _letter_b,
-        _letter_c,
-        _letter_d,
-        _letter_e,
-        _letter_f,
-        _letter_g,
-        _letter_h,
-        _letter_i,
-        _letter_j,
-        _letter_k,
-        _letter_l,
-        _letter_m,
-        _letter_n,
-        _letter_o,
-        _letter_p,
-        _letter_q,
-        _letter_r,
-        _letter_s,
-        _letter_t,
-        _letter_u,
-        _letter_v,
-        _letter_w,
-        _letter_x,
-        _letter_y,
-        _letter_z,
-        _letter_A,
-        _letter_B,
-        _letter_C,
-        _letter_D,
-        _letter_E,
-        _letter_F,
-        _letter_G,
-        _letter_H,
-        _letter_I,
-        _letter_J,
-        _letter_K,
-        _letter_L,
-        _letter_M,
-        _letter_N,
-        _letter_O,
-        _letter_P,
-        _letter_Q,
-        _letter_R,
-        _letter_S,
-        _letter_T,
-        _letter_U,
-        _letter_V,
-        _letter_W,
-        _letter_X,
-        _letter_Y,
-        _letter_Z,
-        _letter_0,
-        _letter_1,
-        _letter_2,
-        _letter_3,
-        _letter_4,
-        _letter_5,
-        _letter_6,
-        _letter_7,
-        _letter_8,
-        _letter_9,
-        _letter__,
-        _letter_open,
-        _letter_close,
-        _letter_quote,
-    ];
+/// Put every glyph into one screen sized bitmap and make it the window's input
+/// region.
+fn draw(
+    conn: Arc<Conn>,
+    screen: &Screen,
+    source: &Source,
+    monitors: &[Monitor],
+    overlay: &Overlay,
+) -> Result<(), OverlayError> {
+    let _scope = trace::scope("draw");
+    // an input-only window can't be a drawable, the root is on the same screen
+    let pixmap = PixmapWrapper::create_pixmap(
+        conn.clone(),
+        1,
+        screen.root,
+        screen.width_in_pixels,
+        screen.height_in_pixels,
+    )?;
 
-    // get conn
-    let (conn1, screen_num): (RustConnection, usize) = x11rb::connect(None).unwrap();
-    let conn = Arc::new(conn1);
+    let gc = GcontextWrapper::create_gc(
+        conn.clone(),
+        pixmap.pixmap(),
+        &CreateGCAux::new().graphics_exposures(0).foreground(0),
+    )?;
 
-    // get screen
-    let screen = &conn.setup().roots[screen_num];
+    let mut img = mask_image(screen.width_in_pixels, screen.height_in_pixels);
 
-    // min screen size
-    if screen.width_in_pixels < 1900 || screen.height_in_pixels < 900 {
-        println!("Screen too small :(, min size 1900 x 900");
-        return;
+    for index in 0..source.len() {
+        let (_pos, _image) = put_char(index, source, monitors, &overlay.stroke, &mut img)?;
+
+        // debug drawing, if there is anything to draw into
+        #[cfg(debug_assertions)]
+        if overlay.mode == Mode::Composited {
+            draw_letter(conn.clone(), _pos, &_image, overlay)?;
+        }
     }
+    {
+        // everything put_char set, in one go
+        let _scope = trace::scope("put_char");
+        shm::put_image(&conn, overlay.shm, &img, pixmap.pixmap(), gc.gcontext())?;
+    }
+
+    create_region(conn, overlay, pixmap.pixmap())?;
+    Ok(())
+}
+
+/// Whether the event means the root size or the monitor arrangement changed.
+fn screen_changed(event: &Event) -> bool {
+    match event {
+        Event::RandrScreenChangeNotify(_) => true,
+        Event::RandrNotify(notify) => notify.sub_code == randr::Notify::CRTC_CHANGE,
+        _ => false,
+    }
+}
+
+/// Move every other window around, once every 100ms.
+#[cfg(not(debug_assertions))]
+fn shake_windows(
+    conn: Arc<Conn>,
+    screen: Screen,
+    atoms: Atoms,
+    win_id: u32,
+) -> Result<(), ReplyError> {
+    let _scope = trace::scope("shake_windows");
+    loop {
+        sleep(Duration::from_millis(100));
+        let tree_reply = conn.query_tree(screen.root)?.reply()?;
+        for child in tree_reply.children {
+            if child == win_id {
+                continue;
+            }
 
-    // check if we support alpha channel
-    let (depth, visual_id): (u8, Visualid) = choose_visual(&conn, screen, screen_num).unwrap();
-    if depth < 32 {
-        // Does not support alpha channel
-        println!("Transparency not supported :(");
-        return;
+            conn.change_property32(
+                PropMode::REPLACE,
+                child,
+                atoms._NET_WM_STATE,
+                AtomEnum::ATOM,
+                &[atoms._NET_WM_STATE_BELOW],
+            )?;
+
+            let values = ConfigureWindowAux::default()
+                .x(rand::thread_rng().gen_range(0..screen.width_in_pixels) as i32)
+                .y(rand::thread_rng().gen_range(0..screen.height_in_pixels) as i32)
+                .width(rand::thread_rng().gen_range(100..screen.width_in_pixels) as u32)
+                .height(rand::thread_rng().gen_range(100..screen.height_in_pixels) as u32);
+            conn.configure_window(child, &values)?;
+        }
+        conn.flush()?;
     }
+}
+
+fn run() -> Result<(), OverlayError> {
+    let args = Args::parse().map_err(OverlayError::Usage)?;
 
-    let compositor = composite_manager_running(&conn, screen_num).unwrap_or(false);
-    if !compositor {
-        println!("No composite manager running :(");
-        return;
+    let source = match (&args.text, &args.pack) {
+        (Some(text), _) => Source::text(text, args.size.unwrap_or(FONT_SIZE))?,
+        (None, Some(path)) => Source::pack(path)?,
+        (None, None) => Source::baked()?,
+    };
+
+    // authentic, but still checked before anything gets drawn, as it will be drawn
+    source.validate(&args.stroke)?;
+
+    if let Command::Render {
+        width,
+        height,
+        path,
+        format,
+    } = &args.command
+    {
+        return headless::render(&source, *width, *height, &args.stroke, path, *format);
     }
 
-    let atoms = Atoms::new(&conn).unwrap();
-    let atoms = atoms.reply().unwrap();
+    // get conn
+    let (conn1, screen_num): (RustConnection, usize) = x11rb::connect(None)?;
+    let conn = Arc::new(Traced::new(conn1, args.trace));
+
+    // get screen, kept up to date when RandR changes its size
+    let mut screen = conn.setup().roots[screen_num].clone();
+
+    // monitors to draw on
+    let mut monitors = select_monitors(&conn, &screen, &args.target, &args)?;
+
+    let (mode, depth, visual_id) = choose_mode(&conn, screen_num)?;
+
+    let atoms = Atoms::new(&conn)?;
+    let atoms = atoms.reply()?;
 
     // enable xfixes (necessary for handling input regions)
-    let _ = conn.xfixes_query_version(2, 0).unwrap();
+    let _ = conn.xfixes_query_version(2, 0)?;
 
     // main window id
-    let win_id = conn.generate_id().unwrap();
+    let win_id = conn.generate_id()?;
 
-    create_window(conn.clone(), screen, visual_id, atoms, win_id, depth);
-
-    let pixmap = PixmapWrapper::create_pixmap(
-        conn.clone(),
-        1,
-        win_id,
-        screen.width_in_pixels,
-        screen.height_in_pixels,
-    )
-    .unwrap();
+    let overlay = Overlay {
+        window: win_id,
+        depth,
+        mode,
+        shm: !args.no_shm && shm::available(&conn),
+        stroke: args.stroke,
+    };
+    if overlay.shm {
+        println!("Uploading images through MIT-SHM");
+    } else {
+        println!("Uploading images over the socket");
+    }
+    create_window(conn.clone(), &screen, visual_id, atoms, &overlay)?;
 
-    let gc = GcontextWrapper::create_gc(
-        conn.clone(),
-        pixmap.pixmap(),
-        &CreateGCAux::new().graphics_exposures(0).foreground(0),
-    )
-    .unwrap();
+    // resolution changes and monitor hotplugs
+    monitors::watch(&conn, screen.root)?;
 
     #[cfg(not(debug_assertions))]
     {
         let conn1 = conn.clone();
         let screen1 = screen.clone();
-        spawn(move || loop {
-            sleep(Duration::from_millis(100));
-            let tree_reply = conn1.query_tree(screen1.root).unwrap().reply().unwrap();
-            for child in tree_reply.children {
-                if child == win_id {
-                    continue;
-                }
-
-                conn1
-                    .change_property32(
-                        PropMode::REPLACE,
-                        child,
-                        atoms._NET_WM_STATE,
-                        AtomEnum::ATOM,
-                        &[atoms._NET_WM_STATE_BELOW],
-                    )
-                    .unwrap();
-
-                let values = ConfigureWindowAux::default()
-                    .x(rand::thread_rng().gen_range(0..screen1.width_in_pixels) as i32)
-                    .y(rand::thread_rng().gen_range(0..screen1.height_in_pixels) as i32)
-                    .width(rand::thread_rng().gen_range(100..screen1.width_in_pixels) as u32)
-                    .height(rand::thread_rng().gen_range(100..screen1.height_in_pixels) as u32);
-                conn1.configure_window(child, &values).unwrap();
-            }
-            conn1.flush().unwrap();
-        });
+        // errors show up in the event loop below as well
+        spawn(move || shake_windows(conn1, screen1, atoms, win_id));
     }
 
-    let mut img = Image::allocate(
-        screen.width_in_pixels,
-        screen.height_in_pixels,
-        ScanlinePad::Pad8,
-        1,
-        BitsPerPixel::B1,
-        ImageOrder::MsbFirst,
-    );
-
-    put_char(
-        conn.clone(),
-        Origin { x: 400, y: 100 },
-        _letter_open,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 200, y: 90 },
-        _letter_x,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 500, y: 110 },
-        _letter_A,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 700, y: 100 },
-        _letter_w,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 1100, y: 110 },
-        _letter__,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 900, y: 100 },
-        _letter_y,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 100, y: 110 },
-        _letter_h,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 1200, y: 140 },
-        _letter_h,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 600, y: 90 },
-        _letter_l,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 1500, y: 120 },
-        _letter__,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 750, y: 410 },
-        _letter_o,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 1000, y: 130 },
-        _letter_s,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 550, y: 420 },
-        _letter_n,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 1300, y: 90 },
-        _letter_a,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 1600, y: 700 },
-        _letter_n,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 800, y: 90 },
-        _letter_4,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 450, y: 410 },
-        _letter_e,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 650, y: 440 },
-        _letter__,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 250, y: 400 },
-        _letter_b,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 1150, y: 430 },
-        _letter_0,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 300, y: 120 },
-        _letter_p,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 950, y: 420 },
-        _letter__,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 850, y: 400 },
-        _letter_N,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 350, y: 430 },
-        _letter_3,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 1500, y: 720 },
-        _letter_3,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 1250, y: 440 },
-        _letter_u,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 1050, y: 410 },
-        _letter_y,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 1100, y: 700 },
-        _letter_S,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 1350, y: 400 },
-        _letter_r,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 1450, y: 410 },
-        _letter__,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 1200, y: 710 },
-        _letter_c,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 1700, y: 710 },
-        _letter_close,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 1400, y: 700 },
-        _letter_E,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 1400, y: 100 },
-        _letter_5,
-        &mut img,
-        win_id,
-    );
-    put_char(
-        conn.clone(),
-        Origin { x: 1300, y: 730 },
-        _letter_r,
-        &mut img,
-        win_id,
-    );
-    img.put(&conn, pixmap.pixmap(), gc.gcontext(), 0, 0)
-        .unwrap();
-
-    create_region(conn.clone(), win_id, pixmap.pixmap());
+    draw(conn.clone(), &screen, &source, &monitors, &overlay)?;
+    if args.once {
+        // wait for the server to process everything
+        conn.get_input_focus()?.reply()?;
+        return Ok(());
+    }
 
     loop {
-        conn.wait_for_event().unwrap();
+        let event = conn.wait_for_event()?;
+        if !screen_changed(&event) {
+            continue;
+        }
+        // a hotplug comes with a burst of events, rebuild only once
+        while conn.poll_for_event()?.is_some() {}
+
+        let geometry = conn.get_geometry(screen.root)?.reply()?;
+        screen.width_in_pixels = geometry.width;
+        screen.height_in_pixels = geometry.height;
+
+        monitors = reselect_monitors(&conn, &screen, &args, &monitors)?;
+
+        conn.configure_window(
+            win_id,
+            &ConfigureWindowAux::new()
+                .width(screen.width_in_pixels as u32)
+                .height(screen.height_in_pixels as u32),
+        )?;
+        draw(conn.clone(), &screen, &source, &monitors, &overlay)?;
+    }
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            println!("{}", e);
+            ExitCode::from(e.exit_code())
+        }
     }
 }
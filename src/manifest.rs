@@ -0,0 +1,65 @@
+// Shared between build.rs, which writes the manifest, and main.rs, which reads it.
+//
+// The manifest is the encrypted list of everything drawn on screen, so neither positions nor
+// drawing order show up in the code. Every entry is 36 bytes, little endian:
+// x: i16, y: i16, then the ticket of the glyph atlas slot drawn there: the slot as u32, sealed
+// with `crypto::glyph_key` of the entry's index and origin. A glyph moved or drawn out of order
+// doesn't get its slot. Which character a slot is only the slot itself knows.
+use crate::crypto::{self, NONCE_LEN, TAG_LEN};
+
+pub const TICKET_LEN: usize = NONCE_LEN + 4 + TAG_LEN;
+pub const ENTRY_LEN: usize = 4 + TICKET_LEN;
+
+#[derive(Copy, Clone)]
+pub struct Entry {
+    pub x: i16,
+    pub y: i16,
+    pub slot: u32,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ManifestError {
+    /// Not a whole number of entries.
+    Length,
+    /// The ticket of entry `index` doesn't open at its origin.
+    Moved { index: usize },
+}
+
+pub fn encode(entries: &[Entry], master: &[u8; 32]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(entries.len() * ENTRY_LEN);
+    for (index, entry) in entries.iter().enumerate() {
+        out.extend(entry.x.to_le_bytes());
+        out.extend(entry.y.to_le_bytes());
+        let key = crypto::glyph_key(master, index, entry.x, entry.y);
+        out.extend(crypto::seal(&key, b"", &entry.slot.to_le_bytes()));
+    }
+    out
+}
+
+pub fn decode(data: &[u8], master: &[u8; 32]) -> Result<Vec<Entry>, ManifestError> {
+    if !data.len().is_multiple_of(ENTRY_LEN) {
+        return Err(ManifestError::Length);
+    }
+    data.chunks_exact(ENTRY_LEN)
+        .enumerate()
+        .map(|(index, e)| decode_entry(index, e, master).ok_or(ManifestError::Moved { index }))
+        .collect()
+}
+
+/// Entry `index` from its `ENTRY_LEN` bytes, `None` if the ticket doesn't open.
+pub fn decode_entry(index: usize, data: &[u8], master: &[u8; 32]) -> Option<Entry> {
+    let (x, y) = (
+        i16::from_le_bytes([data[0], data[1]]),
+        i16::from_le_bytes([data[2], data[3]]),
+    );
+    let slot = crypto::open(
+        &crypto::glyph_key(master, index, x, y),
+        b"",
+        &data[4..ENTRY_LEN],
+    )?;
+    Some(Entry {
+        x,
+        y,
+        slot: u32::from_le_bytes(slot.try_into().ok()?),
+    })
+}
@@ -0,0 +1,80 @@
+// The monitors RandR reports, and keeping up with them.
+use harlem_shake::output::Monitor;
+use x11rb::connection::Connection;
+use x11rb::errors::ReplyError;
+use x11rb::protocol::randr::{self, ConnectionExt as _};
+use x11rb::protocol::xproto::{Screen, Window};
+
+/// The whole root window as a single monitor, for servers without RandR.
+fn root_monitor(screen: &Screen) -> Monitor {
+    Monitor {
+        name: "root".to_string(),
+        x: 0,
+        y: 0,
+        width: screen.width_in_pixels,
+        height: screen.height_in_pixels,
+        primary: true,
+    }
+}
+
+/// All active monitors, left to right. Falls back to the root window if RandR is missing or
+/// reports nothing.
+pub fn monitors(conn: &impl Connection, screen: &Screen) -> Result<Vec<Monitor>, ReplyError> {
+    if conn
+        .extension_information(randr::X11_EXTENSION_NAME)?
+        .is_none()
+    {
+        return Ok(vec![root_monitor(screen)]);
+    }
+    // has to be sent before any other RandR request
+    conn.randr_query_version(1, 3)?.reply()?;
+
+    let resources = conn
+        .randr_get_screen_resources_current(screen.root)?
+        .reply()?;
+    let primary = conn.randr_get_output_primary(screen.root)?.reply()?.output;
+
+    let mut monitors = vec![];
+    for output in resources.outputs {
+        let info = conn
+            .randr_get_output_info(output, resources.config_timestamp)?
+            .reply()?;
+        if info.connection != randr::Connection::CONNECTED || info.crtc == x11rb::NONE {
+            continue;
+        }
+        let crtc = conn
+            .randr_get_crtc_info(info.crtc, resources.config_timestamp)?
+            .reply()?;
+        if crtc.width == 0 || crtc.height == 0 {
+            continue;
+        }
+        monitors.push(Monitor {
+            name: String::from_utf8_lossy(&info.name).into_owned(),
+            x: crtc.x,
+            y: crtc.y,
+            width: crtc.width,
+            height: crtc.height,
+            primary: output == primary,
+        });
+    }
+
+    if monitors.is_empty() {
+        return Ok(vec![root_monitor(screen)]);
+    }
+    monitors.sort_by_key(|m| (m.x, m.y));
+    Ok(monitors)
+}
+
+/// Get notified about resolution changes and monitors coming and going.
+pub fn watch(conn: &impl Connection, root: Window) -> Result<(), ReplyError> {
+    if conn
+        .extension_information(randr::X11_EXTENSION_NAME)?
+        .is_some()
+    {
+        conn.randr_select_input(
+            root,
+            randr::NotifyMask::SCREEN_CHANGE | randr::NotifyMask::CRTC_CHANGE,
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,112 @@
+// Which monitors the message goes on, and which of them shows which glyph. Only geometry, the
+// monitors themselves come from RandR in main.rs.
+use crate::blit::Clip;
+use crate::scale::Transform;
+
+/// Area of the root window shown by one CRTC.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Monitor {
+    pub name: String,
+    pub x: i16,
+    pub y: i16,
+    pub width: u16,
+    pub height: u16,
+    pub primary: bool,
+}
+
+impl Monitor {
+    /// Fits the layout onto this monitor.
+    pub fn transform(&self) -> Transform {
+        Transform::fit(self.x, self.y, self.width, self.height)
+    }
+
+    /// The part of the root window glyphs for this monitor may be drawn in.
+    pub fn clip(&self) -> Clip {
+        Clip {
+            x: self.x as i32,
+            y: self.y as i32,
+            width: self.width as u32,
+            height: self.height as u32,
+        }
+    }
+}
+
+/// Where to put the message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Target {
+    Primary,
+    Output(String),
+    /// The message is laid out across the area of all monitors together, so it reads on from
+    /// one monitor to the next.
+    All,
+}
+
+/// `--output` names an output that isn't there.
+#[derive(Debug, PartialEq, Eq)]
+pub struct NoOutput {
+    pub name: String,
+    pub available: Vec<String>,
+}
+
+/// The monitors the message goes on, out of at least one.
+pub fn select(monitors: Vec<Monitor>, target: &Target) -> Result<Vec<Monitor>, NoOutput> {
+    match target {
+        Target::All => Ok(monitors),
+        // without a primary output, take the leftmost one
+        Target::Primary => Ok(vec![monitors
+            .iter()
+            .find(|m| m.primary)
+            .unwrap_or(&monitors[0])
+            .clone()]),
+        Target::Output(name) => match monitors.iter().find(|m| &m.name == name) {
+            Some(monitor) => Ok(vec![monitor.clone()]),
+            None => Err(NoOutput {
+                name: name.clone(),
+                available: monitors.into_iter().map(|m| m.name).collect(),
+            }),
+        },
+    }
+}
+
+/// The smallest area covering all of `monitors`, the layout is fit into it.
+pub fn area(monitors: &[Monitor]) -> Monitor {
+    let left = monitors.iter().map(|m| m.x as i32).min().unwrap();
+    let top = monitors.iter().map(|m| m.y as i32).min().unwrap();
+    let right = monitors
+        .iter()
+        .map(|m| m.x as i32 + m.width as i32)
+        .max()
+        .unwrap();
+    let bottom = monitors
+        .iter()
+        .map(|m| m.y as i32 + m.height as i32)
+        .max()
+        .unwrap();
+    Monitor {
+        name: monitors
+            .iter()
+            .map(|m| &*m.name)
+            .collect::<Vec<_>>()
+            .join("+"),
+        x: left as i16,
+        y: top as i16,
+        width: (right - left) as u16,
+        height: (bottom - top) as u16,
+        primary: monitors.iter().any(|m| m.primary),
+    }
+}
+
+/// The monitor a glyph centered at `x`, `y` on the root window is shown on, and clipped to: the
+/// one it's on, or in a gap between monitors the closest one.
+pub fn shown_on(monitors: &[Monitor], x: i32, y: i32) -> &Monitor {
+    let distance = |m: &&Monitor| {
+        let dx = (m.x as i32 - x)
+            .max(x - (m.x as i32 + m.width as i32 - 1))
+            .max(0);
+        let dy = (m.y as i32 - y)
+            .max(y - (m.y as i32 + m.height as i32 - 1))
+            .max(0);
+        dx as i64 * dx as i64 + dy as i64 * dy as i64
+    };
+    monitors.iter().min_by_key(distance).unwrap()
+}
@@ -0,0 +1,77 @@
+// Shared between build.rs, which seals the baked in message and, if asked, writes it as a pack,
+// and main.rs, which loads packs with `--pack`.
+//
+// A pack is everything one message needs: its glyph atlas container, followed by its sealed
+// manifest. Both are encrypted with keys derived from the master key, so a pack only loads into
+// builds with the same key, i.e. built with the same HARLEM_SHAKE_SEED. The manifest is sealed
+// with the atlas header and digest as associated data, so it only opens next to its own atlas.
+use crate::atlas::{Atlas, AtlasError, HEADER_LEN};
+use crate::crypto;
+use crate::font::Face;
+use crate::layout::{Placement, Typeface};
+use crate::manifest::{self, Entry};
+use sha2::{Digest, Sha256};
+
+/// The two encrypted assets of a message.
+pub struct Sealed {
+    pub atlas: Vec<u8>,
+    pub manifest: Vec<u8>,
+}
+
+impl Sealed {
+    pub fn to_pack(&self) -> Vec<u8> {
+        let mut pack = self.atlas.clone();
+        pack.extend(&self.manifest);
+        pack
+    }
+}
+
+/// Rasterize every character of `placements` once and encrypt atlas and manifest with keys from
+/// `master`.
+pub fn seal(master: &[u8; 32], placements: &[Placement], face: &Face) -> Sealed {
+    // every character once, in order of first appearance
+    let mut characters: Vec<char> = Vec::new();
+    for placement in placements {
+        if !characters.contains(&placement.character) {
+            characters.push(placement.character);
+        }
+    }
+    let glyphs: Vec<_> = characters
+        .iter()
+        .map(|&c| (c, face.metrics(c), face.rasterize(c)))
+        .collect();
+    let atlas = Atlas::pack(&glyphs).seal(master);
+
+    let entries: Vec<_> = placements
+        .iter()
+        .map(|p| Entry {
+            x: p.origin.x,
+            y: p.origin.y,
+            // in the atlas in the same order
+            slot: characters.iter().position(|&c| c == p.character).unwrap() as u32,
+        })
+        .collect();
+    let manifest = crypto::seal(
+        &crypto::manifest_key(master),
+        &manifest_aad(&atlas),
+        &manifest::encode(&entries, master),
+    );
+    Sealed { atlas, manifest }
+}
+
+/// What the manifest is sealed with next to the atlas container `atlas`: its header and SHA-256.
+pub fn manifest_aad(atlas: &[u8]) -> Vec<u8> {
+    let mut aad = atlas[..HEADER_LEN].to_vec();
+    aad.extend(Sha256::digest(atlas));
+    aad
+}
+
+/// Atlas container and sealed manifest of `pack`. Only the atlas header is checked, decrypting
+/// is up to the caller.
+pub fn split(pack: &[u8]) -> Result<(&[u8], &[u8]), AtlasError> {
+    let len = Atlas::len(pack)?;
+    if len > pack.len() {
+        return Err(AtlasError::NotAnAtlas);
+    }
+    Ok(pack.split_at(len))
+}
@@ -0,0 +1,55 @@
+// The layout is computed for a SCREEN_WIDTH x SCREEN_HEIGHT screen at build time. This maps it,
+// and the glyphs, onto whatever screen we actually run on.
+use crate::layout::{Origin, SCREEN_HEIGHT, SCREEN_WIDTH};
+use image::imageops::{self, FilterType};
+use image::GrayImage;
+
+/// Smallest screen the glyphs are still legible and big enough to hover on by default, a third
+/// of the layout's size.
+pub const DEFAULT_MIN_WIDTH: u16 = SCREEN_WIDTH / 3;
+pub const DEFAULT_MIN_HEIGHT: u16 = SCREEN_HEIGHT / 3;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Transform {
+    pub scale: f32,
+    pub offset_x: i16,
+    pub offset_y: i16,
+}
+
+impl Transform {
+    /// Scale the layout as large as fits into the `width` x `height` area at `x`, `y`, keeping
+    /// its aspect ratio, and center it there.
+    pub fn fit(x: i16, y: i16, width: u16, height: u16) -> Transform {
+        let scale = (width as f32 / SCREEN_WIDTH as f32).min(height as f32 / SCREEN_HEIGHT as f32);
+        Transform {
+            scale,
+            offset_x: x + ((width as f32 - SCREEN_WIDTH as f32 * scale) / 2.0) as i16,
+            offset_y: y + ((height as f32 - SCREEN_HEIGHT as f32 * scale) / 2.0) as i16,
+        }
+    }
+
+    /// Rounds down, so a glyph inside the layout stays inside the screen.
+    pub fn origin(&self, origin: Origin) -> Origin {
+        Origin {
+            x: self.offset_x + (origin.x as f32 * self.scale) as i16,
+            y: self.offset_y + (origin.y as f32 * self.scale) as i16,
+        }
+    }
+
+    pub fn size(&self, width: u32, height: u32) -> (u32, u32) {
+        (
+            ((width as f32 * self.scale) as u32).max(1),
+            ((height as f32 * self.scale) as u32).max(1),
+        )
+    }
+
+    /// Resize a black and white glyph. Edges come out gray, `Stroke` makes it black and white
+    /// again.
+    pub fn glyph(&self, glyph: &GrayImage) -> GrayImage {
+        if self.scale == 1.0 {
+            return glyph.clone();
+        }
+        let (width, height) = self.size(glyph.width(), glyph.height());
+        imageops::resize(glyph, width, height, FilterType::Triangle)
+    }
+}
@@ -0,0 +1,94 @@
+// MIT-SHM upload. A full screen mask, let alone a 4K or multi-monitor one, is a lot to push through
+// the socket in split PutImage requests. Instead the image is written to a file in /dev/shm and
+// its fd handed to the server, which maps it and reads the pixels from there.
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::process;
+use std::sync::atomic::{AtomicU32, Ordering};
+use x11rb::connection::Connection;
+use x11rb::errors::ReplyOrIdError;
+use x11rb::image::Image;
+use x11rb::protocol::shm::{self, ConnectionExt as _};
+use x11rb::protocol::xproto::{Drawable, Gcontext, ImageFormat};
+
+/// A shared memory segment holding `data`, already unlinked so nothing is left behind.
+fn segment(data: &[u8]) -> io::Result<File> {
+    static COUNT: AtomicU32 = AtomicU32::new(0);
+    let path = format!(
+        "/dev/shm/harlem_shake-{}-{}",
+        process::id(),
+        COUNT.fetch_add(1, Ordering::Relaxed)
+    );
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(&path)?;
+    fs::remove_file(&path)?;
+    file.write_all(data)?;
+    Ok(file)
+}
+
+/// Whether the server can map our segments: MIT-SHM 1.2 for passing fds, and a connection fds
+/// can be passed over, so no TCP.
+pub fn available(conn: &impl Connection) -> bool {
+    let probe = || -> Result<(), Box<dyn std::error::Error>> {
+        conn.extension_information(shm::X11_EXTENSION_NAME)?
+            .ok_or("no MIT-SHM")?;
+        let version = conn.shm_query_version()?.reply()?;
+        if (version.major_version, version.minor_version) < (1, 2) {
+            return Err("MIT-SHM too old".into());
+        }
+        let seg = conn.generate_id()?;
+        conn.shm_attach_fd(seg, segment(&[0; 4096])?, true)?
+            .check()?;
+        conn.shm_detach(seg)?;
+        Ok(())
+    };
+    probe().is_ok()
+}
+
+/// Upload `image` to `drawable` at 0, 0, through shared memory if `shm`, else, or if the segment
+/// can't be created, over the socket.
+pub fn put_image(
+    conn: &impl Connection,
+    shm: bool,
+    image: &Image,
+    drawable: Drawable,
+    gc: Gcontext,
+) -> Result<(), ReplyOrIdError> {
+    let segment = match shm {
+        true => {
+            let native = image.native(conn.setup())?;
+            segment(native.data()).ok().map(|file| (native, file))
+        }
+        false => None,
+    };
+    let Some((native, file)) = segment else {
+        image.put(conn, drawable, gc, 0, 0)?;
+        return Ok(());
+    };
+
+    let seg = conn.generate_id()?;
+    conn.shm_attach_fd(seg, file, true)?;
+    conn.shm_put_image(
+        drawable,
+        gc,
+        native.width(),
+        native.height(),
+        0,
+        0,
+        native.width(),
+        native.height(),
+        0,
+        0,
+        native.depth(),
+        ImageFormat::Z_PIXMAP.into(),
+        false,
+        seg,
+        0,
+    )?;
+    // the server is done with the segment once it processed the put
+    conn.shm_detach(seg)?;
+    Ok(())
+}
@@ -0,0 +1,166 @@
+// Where the glyphs come from: the encrypted ones baked in at build time or loaded from a `--pack`,
+// or `--text` rasterized at runtime with the same font, for any string the font covers.
+use crate::error::OverlayError;
+use harlem_shake::atlas::Atlas;
+use harlem_shake::crypto;
+use harlem_shake::font::Face;
+use harlem_shake::layout::{
+    self, Options, Origin, Placement, Problem, Strategy, SCREEN_HEIGHT, SCREEN_WIDTH,
+};
+use harlem_shake::manifest::{self, Entry, ManifestError};
+use harlem_shake::pack;
+use harlem_shake::stroke::Stroke;
+use image::GrayImage;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+pub enum Source {
+    /// The decrypted manifest and the glyph atlas it draws from.
+    Manifest {
+        entries: Vec<Entry>,
+        atlas: Box<Atlas>,
+    },
+    Text {
+        placements: Vec<Placement>,
+        // boxed, it's a lot bigger than a Vec
+        face: Box<Face>,
+    },
+}
+
+impl Source {
+    /// The message baked in by build.rs.
+    pub fn baked() -> Result<Source, OverlayError> {
+        Source::sealed(crate::MANIFEST, crate::ATLAS)
+    }
+
+    /// The message of the pack at `path`, or on stdin for `-`. It has to be sealed with this
+    /// build's key, just like the baked in one.
+    pub fn pack(path: &Path) -> Result<Source, OverlayError> {
+        let read = || {
+            if path == Path::new("-") {
+                let mut data = Vec::new();
+                io::stdin().read_to_end(&mut data)?;
+                Ok(data)
+            } else {
+                fs::read(path)
+            }
+        };
+        let data = read().map_err(|error| OverlayError::Pack {
+            path: path.to_path_buf(),
+            error,
+        })?;
+        let (atlas, manifest) = pack::split(&data).map_err(OverlayError::Atlas)?;
+        Source::sealed(manifest, atlas)
+    }
+
+    fn sealed(manifest: &[u8], atlas: &[u8]) -> Result<Source, OverlayError> {
+        let aad = pack::manifest_aad(atlas);
+        let manifest = crypto::open(&crypto::manifest_key(crate::KEY), &aad, manifest)
+            .ok_or(OverlayError::ManifestAuthFailed)?;
+        let entries = manifest::decode(&manifest, crate::KEY).map_err(|e| match e {
+            ManifestError::Length => OverlayError::ManifestAuthFailed,
+            ManifestError::Moved { index } => OverlayError::MovedGlyph { index },
+        })?;
+        let atlas = Atlas::open(atlas, crate::KEY).map_err(OverlayError::Atlas)?;
+        Ok(Source::Manifest {
+            entries,
+            atlas: Box::new(atlas),
+        })
+    }
+
+    /// `text` at `size` in wrapped lines, laid out like build.rs would without jitter.
+    pub fn text(text: &str, size: f32) -> Result<Source, OverlayError> {
+        let face = Face::with_size(size);
+        if let Some(character) = text.chars().find(|&c| c != ' ' && !face.covers(c)) {
+            return Err(OverlayError::MissingGlyph(character));
+        }
+        let options = Options {
+            strategy: Strategy::Wrapped,
+            seed: 0,
+            jitter: 0,
+        };
+        let placements = layout::layout(text, SCREEN_WIDTH, SCREEN_HEIGHT, &face, &options)
+            .map_err(OverlayError::TextLayout)?;
+        Ok(Source::Text {
+            placements,
+            face: Box::new(face),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Source::Manifest { entries, .. } => entries.len(),
+            Source::Text { placements, .. } => placements.len(),
+        }
+    }
+
+    /// Check where the glyphs are placed, and that `stroke` doesn't grow them into each other or
+    /// the margin.
+    pub fn validate(&self, stroke: &Stroke) -> Result<(), OverlayError> {
+        let glyphs = (0..self.len())
+            .map(|index| self.glyph(index))
+            .collect::<Result<Vec<_>, _>>()?;
+        let found = problems(&glyphs);
+        if !found.is_empty() {
+            return Err(OverlayError::InvalidLayout(found));
+        }
+        // thinning only takes pixels away
+        if stroke.radius <= 0 {
+            return Ok(());
+        }
+
+        // grown like put_char grows them, at layout scale
+        let stroked: Vec<_> = glyphs
+            .iter()
+            .map(|(origin, glyph)| {
+                let (glyph, border) = stroke.apply(glyph, 1.0);
+                let origin = Origin {
+                    x: origin.x - border as i16,
+                    y: origin.y - border as i16,
+                };
+                (origin, glyph)
+            })
+            .collect();
+        let found = problems(&stroked);
+        if !found.is_empty() {
+            return Err(OverlayError::InvalidStroke {
+                radius: stroke.radius,
+                problems: found,
+            });
+        }
+        Ok(())
+    }
+
+    /// Glyph `index` and where it goes in the layout.
+    pub fn glyph(&self, index: usize) -> Result<(Origin, GrayImage), OverlayError> {
+        match self {
+            Source::Manifest { entries, atlas } => {
+                let entry = entries[index];
+                let glyph = atlas
+                    .glyph_at(entry.slot as usize)
+                    .ok_or(OverlayError::InvalidGlyph { index })?;
+                Ok((
+                    Origin {
+                        x: entry.x,
+                        y: entry.y,
+                    },
+                    glyph,
+                ))
+            }
+            Source::Text { placements, face } => {
+                let placement = placements[index];
+                Ok((placement.origin, face.rasterize(placement.character)))
+            }
+        }
+    }
+}
+
+/// Everything wrong with where `glyphs` are placed.
+fn problems(glyphs: &[(Origin, GrayImage)]) -> Vec<Problem> {
+    let glyphs: Vec<_> = glyphs
+        .iter()
+        .map(|(origin, glyph)| (*origin, glyph))
+        .collect();
+    layout::validate(&glyphs, SCREEN_WIDTH, SCREEN_HEIGHT)
+}
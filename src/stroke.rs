@@ -0,0 +1,90 @@
+// How thick the clickable glyphs are. Thin strokes can be close to impossible to hover, so the
+// mask can be grown, or shrunk for a harder challenge, before it goes to the X server.
+use image::GrayImage;
+
+/// Default luma a pixel needs to be set. Below half, so thin strokes survive shrinking.
+pub const THRESHOLD: u8 = 96;
+/// Largest radius either way. Far past it glyphs are blobs running into each other, and the
+/// grown bitmaps get slow to compute and no longer fit screen coordinates.
+pub const MAX_RADIUS: i16 = 50;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Stroke {
+    /// Luma a pixel needs to be part of the glyph, 1 to 255.
+    pub threshold: u8,
+    /// Pixels added around every stroke, in layout pixels. Negative values take pixels away.
+    /// At most `MAX_RADIUS` either way.
+    pub radius: i16,
+}
+
+impl Default for Stroke {
+    fn default() -> Stroke {
+        Stroke {
+            threshold: THRESHOLD,
+            radius: 0,
+        }
+    }
+}
+
+impl Stroke {
+    /// `glyph` made black and white by the threshold, then dilated or eroded by the radius times
+    /// `scale`. Dilating grows the image, the second value is by how much on every side.
+    pub fn apply(&self, glyph: &GrayImage, scale: f32) -> (GrayImage, u32) {
+        let radius = (self.radius as f32 * scale).round() as i32;
+        let (width, height) = (glyph.width() as usize, glyph.height() as usize);
+        let mut set: Vec<bool> = glyph.pixels().map(|p| p.0[0] >= self.threshold).collect();
+        // nothing to grow or shrink in a glyph without pixels, like space
+        if radius == 0 || set.is_empty() {
+            return (image(&set, width, height), 0);
+        }
+
+        // a square is separable: rows first, then columns
+        let (r, grow) = (radius.unsigned_abs() as usize, radius > 0);
+        let out_width = if grow { width + 2 * r } else { width };
+        let out_height = if grow { height + 2 * r } else { height };
+
+        let mut rows = Vec::with_capacity(out_width * height);
+        for row in set.chunks_exact(width) {
+            rows.extend(line(row, r, grow));
+        }
+        set = vec![false; out_width * out_height];
+        let mut column = Vec::with_capacity(height);
+        for x in 0..out_width {
+            column.clear();
+            column.extend((0..height).map(|y| rows[y * out_width + x]));
+            for (y, pixel) in line(&column, r, grow).into_iter().enumerate() {
+                set[y * out_width + x] = pixel;
+            }
+        }
+        let border = if grow { r as u32 } else { 0 };
+        (image(&set, out_width, out_height), border)
+    }
+}
+
+/// Dilate a line of pixels by `r`, making it `2 * r` longer, or erode it, counting everything
+/// outside as unset.
+fn line(pixels: &[bool], r: usize, grow: bool) -> Vec<bool> {
+    // set[i..j] has prefix[j] - prefix[i] pixels set
+    let mut prefix = Vec::with_capacity(pixels.len() + 1);
+    prefix.push(0);
+    for &pixel in pixels {
+        prefix.push(prefix.last().unwrap() + pixel as usize);
+    }
+    let count = |start: usize, end: usize| prefix[end.min(pixels.len())] - prefix[start];
+
+    if grow {
+        // output pixel k is pixel k - r of the input, its window k - 2r ..= k
+        (0..pixels.len() + 2 * r)
+            .map(|k| count(k.saturating_sub(2 * r), k + 1) > 0)
+            .collect()
+    } else {
+        (0..pixels.len())
+            .map(|k| k >= r && k + r < pixels.len() && count(k - r, k + r + 1) == 2 * r + 1)
+            .collect()
+    }
+}
+
+fn image(set: &[bool], width: usize, height: usize) -> GrayImage {
+    let pixels = set.iter().map(|&set| if set { 255 } else { 0 }).collect();
+    GrayImage::from_raw(width as u32, height as u32, pixels).unwrap()
+}
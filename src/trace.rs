@@ -0,0 +1,248 @@
+// `--trace`: log every request the overlay sends, decoded, the way a protocol sniffer like xscope
+// would see it. Handy to check what leaks.
+use std::cell::Cell;
+use std::io::IoSlice;
+use std::sync::Mutex;
+use x11rb::connection::{
+    BufWithFds, Connection, DiscardMode, RawEventAndSeqNumber, ReplyOrError, RequestConnection,
+    RequestKind, SequenceNumber,
+};
+use x11rb::cookie::{Cookie, CookieWithFds, VoidCookie};
+use x11rb::errors::{ConnectionError, ParseError, ReplyOrIdError};
+use x11rb::protocol::xproto::Setup;
+use x11rb::protocol::{Event, Request};
+use x11rb::utils::RawFdContainer;
+use x11rb::x11_utils::{
+    parse_request_header, BigRequests, ExtInfoProvider, ExtensionInformation, TryParse, TryParseFd,
+    X11Error,
+};
+
+/// Longer decoded requests, i.e. image data, are cut off. The size is logged in full.
+const MAX_LOGGED: usize = 300;
+
+thread_local! {
+    static SCOPE: Cell<&'static str> = const { Cell::new("") };
+}
+
+/// Requests sent on this thread are logged as coming from `name`, until the guard is dropped.
+pub fn scope(name: &'static str) -> Scope {
+    Scope(SCOPE.replace(name))
+}
+
+pub struct Scope(&'static str);
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        SCOPE.set(self.0);
+    }
+}
+
+/// Forwards everything to `inner`, logging requests to stderr if enabled.
+pub struct Traced<C> {
+    inner: C,
+    enabled: bool,
+    /// Every extension asked for so far, to tell extension requests apart by major opcode.
+    extensions: Mutex<Vec<(&'static str, ExtensionInformation)>>,
+}
+
+impl<C: RequestConnection> Traced<C> {
+    pub fn new(inner: C, enabled: bool) -> Traced<C> {
+        Traced {
+            inner,
+            enabled,
+            extensions: Mutex::new(vec![]),
+        }
+    }
+
+    fn log(&self, bufs: &[IoSlice<'_>], sequence: SequenceNumber) {
+        if !self.enabled {
+            return;
+        }
+        let request: Vec<u8> = bufs.iter().flat_map(|buf| buf.iter().copied()).collect();
+        let extensions = self.extensions.lock().unwrap();
+        let decoded = parse_request_header(&request, BigRequests::Enabled)
+            .and_then(|(header, body)| {
+                Request::parse(header, body, &mut vec![], &Extensions(&extensions))
+            })
+            .map(|request| format!("{:?}", request))
+            .unwrap_or_else(|e| format!("undecodable: {:?}", e));
+
+        let mut line: String = decoded.chars().take(MAX_LOGGED).collect();
+        if line.len() < decoded.len() {
+            line.push_str(" ...");
+        }
+        let scope = SCOPE.get();
+        let scope = if scope.is_empty() { "-" } else { scope };
+        eprintln!(
+            "trace #{} [{}] {} bytes: {}",
+            sequence,
+            scope,
+            request.len(),
+            line
+        );
+    }
+}
+
+struct Extensions<'a>(&'a [(&'static str, ExtensionInformation)]);
+
+impl ExtInfoProvider for Extensions<'_> {
+    fn get_from_major_opcode(&self, major_opcode: u8) -> Option<(&str, ExtensionInformation)> {
+        self.0
+            .iter()
+            .find(|(_, info)| info.major_opcode == major_opcode)
+            .copied()
+    }
+
+    // only requests are decoded
+    fn get_from_event_code(&self, _event_code: u8) -> Option<(&str, ExtensionInformation)> {
+        None
+    }
+
+    fn get_from_error_code(&self, _error_code: u8) -> Option<(&str, ExtensionInformation)> {
+        None
+    }
+}
+
+impl<C: RequestConnection> RequestConnection for Traced<C> {
+    type Buf = C::Buf;
+
+    fn send_request_with_reply<R>(
+        &self,
+        bufs: &[IoSlice<'_>],
+        fds: Vec<RawFdContainer>,
+    ) -> Result<Cookie<'_, Self, R>, ConnectionError>
+    where
+        R: TryParse,
+    {
+        let cookie = self.inner.send_request_with_reply::<R>(bufs, fds)?;
+        let sequence = cookie.sequence_number();
+        // the reply is collected through us now, the inner cookie must not discard it on drop
+        std::mem::forget(cookie);
+        self.log(bufs, sequence);
+        Ok(Cookie::new(self, sequence))
+    }
+
+    fn send_request_with_reply_with_fds<R>(
+        &self,
+        bufs: &[IoSlice<'_>],
+        fds: Vec<RawFdContainer>,
+    ) -> Result<CookieWithFds<'_, Self, R>, ConnectionError>
+    where
+        R: TryParseFd,
+    {
+        let cookie = self
+            .inner
+            .send_request_with_reply_with_fds::<R>(bufs, fds)?;
+        let sequence = cookie.sequence_number();
+        std::mem::forget(cookie);
+        self.log(bufs, sequence);
+        Ok(CookieWithFds::new(self, sequence))
+    }
+
+    fn send_request_without_reply(
+        &self,
+        bufs: &[IoSlice<'_>],
+        fds: Vec<RawFdContainer>,
+    ) -> Result<VoidCookie<'_, Self>, ConnectionError> {
+        let cookie = self.inner.send_request_without_reply(bufs, fds)?;
+        let sequence = cookie.sequence_number();
+        std::mem::forget(cookie);
+        self.log(bufs, sequence);
+        Ok(VoidCookie::new(self, sequence))
+    }
+
+    fn discard_reply(&self, sequence: SequenceNumber, kind: RequestKind, mode: DiscardMode) {
+        self.inner.discard_reply(sequence, kind, mode)
+    }
+
+    fn prefetch_extension_information(
+        &self,
+        extension_name: &'static str,
+    ) -> Result<(), ConnectionError> {
+        self.inner.prefetch_extension_information(extension_name)
+    }
+
+    fn extension_information(
+        &self,
+        extension_name: &'static str,
+    ) -> Result<Option<ExtensionInformation>, ConnectionError> {
+        let info = self.inner.extension_information(extension_name)?;
+        if let Some(info) = info {
+            let mut extensions = self.extensions.lock().unwrap();
+            if !extensions.iter().any(|(name, _)| *name == extension_name) {
+                extensions.push((extension_name, info));
+            }
+        }
+        Ok(info)
+    }
+
+    fn wait_for_reply_or_raw_error(
+        &self,
+        sequence: SequenceNumber,
+    ) -> Result<ReplyOrError<Self::Buf>, ConnectionError> {
+        self.inner.wait_for_reply_or_raw_error(sequence)
+    }
+
+    fn wait_for_reply(
+        &self,
+        sequence: SequenceNumber,
+    ) -> Result<Option<Self::Buf>, ConnectionError> {
+        self.inner.wait_for_reply(sequence)
+    }
+
+    fn wait_for_reply_with_fds_raw(
+        &self,
+        sequence: SequenceNumber,
+    ) -> Result<ReplyOrError<BufWithFds<Self::Buf>, Self::Buf>, ConnectionError> {
+        self.inner.wait_for_reply_with_fds_raw(sequence)
+    }
+
+    fn check_for_raw_error(
+        &self,
+        sequence: SequenceNumber,
+    ) -> Result<Option<Self::Buf>, ConnectionError> {
+        self.inner.check_for_raw_error(sequence)
+    }
+
+    fn prefetch_maximum_request_bytes(&self) {
+        self.inner.prefetch_maximum_request_bytes()
+    }
+
+    fn maximum_request_bytes(&self) -> usize {
+        self.inner.maximum_request_bytes()
+    }
+
+    fn parse_error(&self, error: &[u8]) -> Result<X11Error, ParseError> {
+        self.inner.parse_error(error)
+    }
+
+    fn parse_event(&self, event: &[u8]) -> Result<Event, ParseError> {
+        self.inner.parse_event(event)
+    }
+}
+
+impl<C: Connection> Connection for Traced<C> {
+    fn wait_for_raw_event_with_sequence(
+        &self,
+    ) -> Result<RawEventAndSeqNumber<Self::Buf>, ConnectionError> {
+        self.inner.wait_for_raw_event_with_sequence()
+    }
+
+    fn poll_for_raw_event_with_sequence(
+        &self,
+    ) -> Result<Option<RawEventAndSeqNumber<Self::Buf>>, ConnectionError> {
+        self.inner.poll_for_raw_event_with_sequence()
+    }
+
+    fn flush(&self) -> Result<(), ConnectionError> {
+        self.inner.flush()
+    }
+
+    fn setup(&self) -> &Setup {
+        self.inner.setup()
+    }
+
+    fn generate_id(&self) -> Result<u32, ReplyOrIdError> {
+        self.inner.generate_id()
+    }
+}
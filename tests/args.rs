@@ -0,0 +1,32 @@
+// What the command line refuses before anything is drawn or connected to.
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+#[path = "common/render.rs"]
+mod render;
+use render::{render, render_at};
+
+#[test]
+fn sizes_must_not_be_empty() {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("args-size.pbm");
+    assert_eq!(render(&path, &[], b""), Some(0));
+    for size in [
+        "0x0",
+        "0x900",
+        "1900x0",
+        "1900",
+        "x",
+        "-1x900",
+        "1900x900x1",
+    ] {
+        assert_eq!(render_at(size, &path, &[], b""), Some(2), "{}", size);
+    }
+
+    // refused before connecting, so no X server needed
+    let status = Command::new(env!("CARGO_BIN_EXE_harlem_shake"))
+        .args(["--min-size", "0x0"])
+        .stdout(Stdio::null())
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(2));
+}
@@ -0,0 +1,195 @@
+// What `seal` writes, `open` reads back, and anything else is turned away.
+use harlem_shake::atlas::{Atlas, AtlasError, HEADER_LEN, INDEX_ENTRY_LEN, VERSION};
+use harlem_shake::crypto::{self, NONCE_LEN};
+use harlem_shake::font::Face;
+use harlem_shake::layout::Typeface;
+use image::ImageFormat;
+use std::io::Cursor;
+
+const KEY: &[u8; 32] = &[7; 32];
+
+fn sealed() -> (Atlas, Vec<u8>) {
+    let face = Face::new();
+    let glyphs: Vec<_> = "hxp{Wy_}"
+        .chars()
+        .map(|c| (c, face.metrics(c), face.rasterize(c)))
+        .collect();
+    let atlas = Atlas::pack(&glyphs);
+    let sealed = atlas.seal(KEY);
+    (atlas, sealed)
+}
+
+#[test]
+fn round_trip() {
+    let face = Face::new();
+    let (atlas, mut sealed) = sealed();
+    // anything after the container is ignored
+    sealed.extend(b"trailing");
+    let opened = Atlas::open(&sealed, KEY).unwrap();
+    assert_eq!(opened.slots, atlas.slots);
+    for c in "hxp{Wy_}".chars() {
+        assert_eq!(opened.glyph(c).unwrap(), face.rasterize(c));
+        assert_eq!(opened.slot(c).unwrap().metrics, face.metrics(c));
+    }
+    assert!(opened.glyph('z').is_none());
+}
+
+#[test]
+fn bad_containers_are_rejected() {
+    let (_, sealed) = sealed();
+    let key = KEY;
+
+    let mut tampered = sealed.clone();
+    tampered[HEADER_LEN + NONCE_LEN + 3] ^= 1;
+    assert!(matches!(
+        Atlas::open(&tampered, key),
+        Err(AtlasError::IndexAuthFailed)
+    ));
+    assert!(matches!(
+        Atlas::open(&sealed, &[8; 32]),
+        Err(AtlasError::IndexAuthFailed)
+    ));
+
+    let mut newer = sealed.clone();
+    newer[4..6].copy_from_slice(&(VERSION + 1).to_le_bytes());
+    assert!(matches!(
+        Atlas::open(&newer, key),
+        Err(AtlasError::Version(v)) if v == VERSION + 1
+    ));
+    let mut reserved = sealed.clone();
+    reserved[6] = 1;
+    assert!(matches!(
+        Atlas::open(&reserved, key),
+        Err(AtlasError::NotAnAtlas)
+    ));
+    // the header is authenticated with the index, a longer container doesn't open either
+    let mut longer = sealed.clone();
+    let len = u32::from_le_bytes(longer[8..12].try_into().unwrap()) + 1;
+    longer[8..12].copy_from_slice(&len.to_le_bytes());
+    longer.push(0);
+    assert!(matches!(
+        Atlas::open(&longer, key),
+        Err(AtlasError::IndexAuthFailed)
+    ));
+    assert!(matches!(
+        Atlas::open(&sealed[1..], key),
+        Err(AtlasError::NotAnAtlas)
+    ));
+    assert!(matches!(
+        Atlas::open(&sealed[..sealed.len() - 1], key),
+        Err(AtlasError::NotAnAtlas)
+    ));
+}
+
+/// `sealed` with its index and blobs changed by `edit`, and sealed again with the atlas key, like
+/// someone who lifted that key alone could.
+fn reseal(sealed: &[u8], edit: impl FnOnce(&mut [Vec<u8>], &mut [Vec<u8>])) -> Vec<u8> {
+    let index_len = u32::from_le_bytes(sealed[12..16].try_into().unwrap()) as usize;
+    let (index, mut rest) = sealed[HEADER_LEN..].split_at(index_len);
+    let key = crypto::atlas_key(KEY);
+    let index = crypto::open(&key, &sealed[..HEADER_LEN], index).unwrap();
+    let (head, entries) = index.split_at(8);
+    let mut entries: Vec<_> = entries
+        .chunks(INDEX_ENTRY_LEN)
+        .map(<[u8]>::to_vec)
+        .collect();
+    let mut blobs = vec![];
+    for entry in &entries {
+        let len = u32::from_le_bytes(entry[16..20].try_into().unwrap()) as usize;
+        let (blob, next) = rest.split_at(len);
+        blobs.push(blob.to_vec());
+        rest = next;
+    }
+
+    edit(&mut entries, &mut blobs);
+    for (entry, blob) in entries.iter_mut().zip(&blobs) {
+        entry[16..20].copy_from_slice(&(blob.len() as u32).to_le_bytes());
+    }
+    let index = [head.to_vec(), entries.concat()].concat();
+    let index_len = index_len as u32;
+    let len = index_len + blobs.iter().map(|b| b.len() as u32).sum::<u32>();
+    let mut out = sealed[..8].to_vec();
+    out.extend(len.to_le_bytes());
+    out.extend(index_len.to_le_bytes());
+    let index = crypto::seal(&key, &out, &index);
+    out.extend(index);
+    out.extend(blobs.concat());
+    out
+}
+
+#[test]
+fn glyphs_only_open_in_their_own_slot() {
+    let (_, sealed) = sealed();
+    // resealing an unchanged index changes nothing
+    assert!(Atlas::open(&reseal(&sealed, |_, _| ()), KEY).is_ok());
+
+    let mut flipped = sealed.clone();
+    *flipped.last_mut().unwrap() ^= 1;
+    assert!(matches!(
+        Atlas::open(&flipped, KEY),
+        Err(AtlasError::AuthFailed { index: 7 })
+    ));
+    // the pixels of two characters swapped
+    let swapped = reseal(&sealed, |_, blobs| blobs.swap(0, 1));
+    assert!(matches!(
+        Atlas::open(&swapped, KEY),
+        Err(AtlasError::AuthFailed { index: 0 })
+    ));
+    // two glyphs' metrics swapped in the index, the pixels left in place
+    let remeasured = reseal(&sealed, |entries, _| {
+        let (a, b) = entries.split_at_mut(1);
+        a[0][4..16].swap_with_slice(&mut b[0][4..16]);
+    });
+    assert!(matches!(
+        Atlas::open(&remeasured, KEY),
+        Err(AtlasError::AuthFailed { index: 0 })
+    ));
+    // a whole slot moved, entry and pixels
+    let moved = reseal(&sealed, |entries, blobs| {
+        entries.swap(0, 1);
+        blobs.swap(0, 1);
+    });
+    assert!(matches!(
+        Atlas::open(&moved, KEY),
+        Err(AtlasError::AuthFailed { index: 0 })
+    ));
+    // authentic, but wider than the atlas
+    let widened = reseal(&sealed, |entries, _| {
+        entries[1][4..6].copy_from_slice(&u16::MAX.to_le_bytes())
+    });
+    assert!(matches!(
+        Atlas::open(&widened, KEY),
+        Err(AtlasError::Corrupt)
+    ));
+}
+
+#[test]
+fn smaller_than_png() {
+    // every character of the default flag, like build.rs packs it
+    let face = Face::new();
+    let mut characters: Vec<char> = "hxp{Alw4ys_ha5_b3en_oN_y0ur_ScrE3n}".chars().collect();
+    characters.sort();
+    characters.dedup();
+    let glyphs: Vec<_> = characters
+        .iter()
+        .map(|&c| (c, face.metrics(c), face.rasterize(c)))
+        .collect();
+    let atlas = Atlas::pack(&glyphs);
+    let sealed = atlas.seal(KEY);
+
+    let mut png = Cursor::new(Vec::new());
+    atlas.image.write_to(&mut png, ImageFormat::Png).unwrap();
+    let mut blobs = 0;
+    for (_, _, bitmap) in &glyphs {
+        let mut png = Cursor::new(Vec::new());
+        bitmap.write_to(&mut png, ImageFormat::Png).unwrap();
+        blobs += png.get_ref().len();
+    }
+    println!(
+        "deflated atlas: {} bytes, atlas PNG alone: {}, one PNG per glyph: {}",
+        sealed.len(),
+        png.get_ref().len(),
+        blobs
+    );
+    assert!(sealed.len() < png.get_ref().len());
+}
@@ -0,0 +1,134 @@
+// The row blits must set exactly the pixels a `put_pixel` loop would, wherever the glyph lands:
+// partly off every edge of the image or the clip, and not a whole number of bytes wide.
+use harlem_shake::blit::{self, Clip};
+use harlem_shake::stroke::THRESHOLD;
+use image::{GrayImage, Luma};
+use x11rb::image::{BitsPerPixel, Image, ImageOrder, ScanlinePad};
+
+const WIDTH: u16 = 40;
+const HEIGHT: u16 = 30;
+const ON: u32 = 0x88000000 | (255 * 255);
+const OFF: u32 = 0x88000000;
+
+/// 13 x 11 of noise, with lumas on both sides of the threshold.
+fn glyph() -> GrayImage {
+    let mut state = 1u32;
+    GrayImage::from_fn(13, 11, |_, _| {
+        state = state.wrapping_mul(1103515245).wrapping_add(12345);
+        Luma([(state >> 16) as u8])
+    })
+}
+
+/// An image with some bits already set, which the blits have to leave alone.
+fn image(bits_per_pixel: BitsPerPixel, order: ImageOrder) -> Image<'static> {
+    let (depth, pad) = match bits_per_pixel {
+        BitsPerPixel::B1 => (1, ScanlinePad::Pad8),
+        _ => (32, ScanlinePad::Pad32),
+    };
+    let mut image = Image::allocate(WIDTH, HEIGHT, pad, depth, bits_per_pixel, order);
+    for (i, byte) in image.data_mut().iter_mut().enumerate() {
+        *byte = (i * 37 % 11) as u8;
+    }
+    image
+}
+
+fn clips() -> [Clip; 3] {
+    let inner = Clip {
+        x: 5,
+        y: 3,
+        width: 20,
+        height: 17,
+    };
+    // reaching past the image on every side
+    let outer = Clip {
+        x: -7,
+        y: -7,
+        width: 100,
+        height: 100,
+    };
+    let whole = Clip {
+        x: 0,
+        y: 0,
+        width: WIDTH as u32,
+        height: HEIGHT as u32,
+    };
+    [whole, inner, outer]
+}
+
+/// Where the glyph at `x`, `y` is drawn, pixel by pixel.
+fn visible(x: i32, y: i32, clip: Clip) -> impl Iterator<Item = (u32, u32, u16, u16)> {
+    let glyph = glyph();
+    let (width, height) = glyph.dimensions();
+    (0..height)
+        .flat_map(move |gy| (0..width).map(move |gx| (gx, gy)))
+        .filter_map(move |(gx, gy)| {
+            let (tx, ty) = (x + gx as i32, y + gy as i32);
+            let inside = |at: i32, start: i32, len: u32, image: u16| {
+                at >= start && at < start + len as i32 && at >= 0 && at < image as i32
+            };
+            (inside(tx, clip.x, clip.width, WIDTH) && inside(ty, clip.y, clip.height, HEIGHT))
+                .then_some((gx, gy, tx as u16, ty as u16))
+        })
+}
+
+/// Every position with the glyph partly or entirely off each edge.
+fn positions() -> impl Iterator<Item = (i32, i32)> {
+    (-14..=WIDTH as i32 + 1).flat_map(|x| (-12..=HEIGHT as i32 + 1).map(move |y| (x, y)))
+}
+
+#[test]
+fn mask_matches_put_pixel() {
+    let glyph = glyph();
+    for order in [ImageOrder::MsbFirst, ImageOrder::LsbFirst] {
+        for clip in clips() {
+            for (x, y) in positions() {
+                let (mut expected, mut blitted) = (
+                    image(BitsPerPixel::B1, order),
+                    image(BitsPerPixel::B1, order),
+                );
+                for (gx, gy, tx, ty) in visible(x, y, clip) {
+                    if glyph.get_pixel(gx, gy).0[0] >= THRESHOLD {
+                        expected.put_pixel(tx, ty, 1);
+                    }
+                }
+                blit::mask(&mut blitted, &glyph, x, y, clip, THRESHOLD);
+                assert!(
+                    expected.data() == blitted.data(),
+                    "{:?} at {}, {} in {:?}",
+                    order,
+                    x,
+                    y,
+                    clip
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn argb_matches_put_pixel() {
+    let glyph = glyph();
+    for order in [ImageOrder::MsbFirst, ImageOrder::LsbFirst] {
+        for clip in clips() {
+            for (x, y) in positions() {
+                let (mut expected, mut blitted) = (
+                    image(BitsPerPixel::B32, order),
+                    image(BitsPerPixel::B32, order),
+                );
+                for (gx, gy, tx, ty) in visible(x, y, clip) {
+                    let on = glyph.get_pixel(gx, gy).0[0] >= THRESHOLD;
+                    expected.put_pixel(tx, ty, if on { ON } else { OFF });
+                }
+                blit::argb(&mut blitted, &glyph, x, y, clip, THRESHOLD, ON, OFF);
+                assert!(
+                    expected.data() == blitted.data(),
+                    "{:?} at {}, {} in {:?}",
+                    order,
+                    x,
+                    y,
+                    clip
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,25 @@
+// `harlem_shake render` of the binary under test, shared by the tests with `#[path]`.
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Run `harlem_shake render 1900x900 PATH ARGS...` with `stdin` piped in, and return its exit
+/// code.
+pub fn render(path: &Path, args: &[&str], stdin: &[u8]) -> Option<i32> {
+    render_at("1900x900", path, args, stdin)
+}
+
+/// The same with the size as given on the command line.
+pub fn render_at(size: &str, path: &Path, args: &[&str], stdin: &[u8]) -> Option<i32> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_harlem_shake"))
+        .args(["render", size])
+        .arg(path)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    // it may be gone before reading everything, when it refuses the arguments
+    let _ = child.stdin.take().unwrap().write_all(stdin);
+    child.wait().unwrap().code()
+}
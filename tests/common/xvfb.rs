@@ -0,0 +1,56 @@
+// Throwaway X servers, shared by the tests and benches with `#[path]`.
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Killed on drop, so a failing test doesn't leave servers behind.
+pub struct Process(pub Child);
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+pub struct Xvfb {
+    _process: Process,
+    pub display: u16,
+}
+
+impl Xvfb {
+    /// Start Xvfb with a `width` x `height` root on a free display, `None` if it isn't installed.
+    pub fn start(width: u16, height: u16) -> Option<Xvfb> {
+        let display = (90..200).find(|n| {
+            !Path::new(&format!("/tmp/.X11-unix/X{}", n)).exists()
+                && !Path::new(&format!("/tmp/.X{}-lock", n)).exists()
+        })?;
+        let child = Command::new("Xvfb")
+            .arg(format!(":{}", display))
+            .args(["-screen", "0", &format!("{}x{}x24", width, height)])
+            .args(["-nolisten", "tcp"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        let xvfb = Xvfb {
+            _process: Process(child),
+            display,
+        };
+
+        let start = Instant::now();
+        while !Path::new(&xvfb.socket()).exists() {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "Xvfb didn't come up"
+            );
+            thread::sleep(Duration::from_millis(50));
+        }
+        Some(xvfb)
+    }
+
+    pub fn socket(&self) -> String {
+        format!("/tmp/.X11-unix/X{}", self.display)
+    }
+}
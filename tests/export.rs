@@ -0,0 +1,43 @@
+// The bitmap formats byte for byte: header, rows padded to whole bytes, and which end of a byte
+// the leftmost pixel goes to.
+use harlem_shake::export::{self, Format};
+use image::{GrayImage, Luma};
+use std::fs;
+use std::path::PathBuf;
+
+/// 10 x 2, so every row has 6 bits of padding.
+fn mask() -> GrayImage {
+    let mut mask = GrayImage::new(10, 2);
+    for (x, y) in [(0, 0), (8, 0), (3, 1)] {
+        mask.put_pixel(x, y, Luma([255]));
+    }
+    // any ink counts
+    mask.put_pixel(9, 1, Luma([1]));
+    mask
+}
+
+fn written(format: Format, name: &str) -> Vec<u8> {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    export::write(&mask(), format, &path).unwrap();
+    fs::read(path).unwrap()
+}
+
+#[test]
+fn pbm_is_msb_first() {
+    let mut expected = b"P4\n10 2\n".to_vec();
+    expected.extend([0x80, 0x80, 0x10, 0x40]);
+    assert_eq!(written(Format::Pbm, "export.pbm"), expected);
+}
+
+#[test]
+fn xbm_is_lsb_first() {
+    let expected = "#define harlem_shake_width 10\n\
+                    #define harlem_shake_height 2\n\
+                    static unsigned char harlem_shake_bits[] = {\n  \
+                    0x01, 0x01, 0x08, 0x02\n\
+                    };\n";
+    assert_eq!(
+        String::from_utf8(written(Format::Xbm, "export.xbm")).unwrap(),
+        expected
+    );
+}
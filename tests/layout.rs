@@ -0,0 +1,221 @@
+// Whatever the message, strategy and seed, `layout` must only place glyphs `validate` accepts.
+use harlem_shake::font::Face;
+use harlem_shake::layout::{
+    self, LayoutError, Options, Origin, Placement, Problem, Strategy, Typeface, SCREEN_HEIGHT,
+    SCREEN_WIDTH,
+};
+use image::{GrayImage, Luma};
+
+const MESSAGES: &[&str] = &[
+    "hxp{Alw4ys_ha5_b3en_oN_y0ur_ScrE3n}",
+    "x",
+    "a_very_long_flag_that_needs_all_five_lines_of_the_screen_to_fit_on_it_somehow_or_other",
+    "words with spaces break at spaces",
+    "AVATAR To Wally, typeset: fjord {quirky_glyphs}",
+];
+
+#[test]
+fn layouts_are_valid() {
+    let face = Face::new();
+    let (mut laid_out, mut failed) = (0, vec![]);
+    for message in MESSAGES {
+        for strategy in [Strategy::SingleLine, Strategy::Wrapped, Strategy::Scrambled] {
+            for seed in 0..50 {
+                let options = Options {
+                    strategy,
+                    seed,
+                    jitter: 200,
+                };
+                let placements =
+                    match layout::layout(message, SCREEN_WIDTH, SCREEN_HEIGHT, &face, &options) {
+                        Ok(placements) => placements,
+                        Err(e) => {
+                            failed.push((*message, strategy, e));
+                            continue;
+                        }
+                    };
+                let bitmaps: Vec<_> = placements
+                    .iter()
+                    .map(|p| face.rasterize(p.character))
+                    .collect();
+                let glyphs: Vec<_> = placements.iter().map(|p| p.origin).zip(&bitmaps).collect();
+                let problems = layout::validate(&glyphs, SCREEN_WIDTH, SCREEN_HEIGHT);
+                assert!(
+                    problems.is_empty(),
+                    "{:?} {:?}: {:?}",
+                    message,
+                    options,
+                    problems
+                );
+                laid_out += 1;
+            }
+        }
+    }
+
+    // only a single line gives up, on the messages too wide for one, whatever the seed
+    assert!(
+        failed
+            .iter()
+            .all(|(_, strategy, e)| *strategy == Strategy::SingleLine
+                && matches!(e, LayoutError::TooWide { .. })),
+        "{:?}",
+        failed
+    );
+    assert_eq!(laid_out, 550);
+}
+
+#[test]
+fn kerning_pulls_glyphs_together() {
+    let face = Face::new();
+    let options = Options {
+        strategy: Strategy::SingleLine,
+        seed: 0,
+        jitter: 0,
+    };
+    let placements = layout::layout("AV", SCREEN_WIDTH, SCREEN_HEIGHT, &face, &options).unwrap();
+    let (a, v) = (placements[0].origin, placements[1].origin);
+    let advance = face.metrics('A').advance;
+    let bearings = face.metrics('V').bearing_x - face.metrics('A').bearing_x;
+    let kerned = (v.x - a.x - bearings) as f32;
+    assert!(face.kern('A', 'V') < 0.0);
+    assert!((kerned - (advance + face.kern('A', 'V'))).abs() <= 1.0);
+}
+
+#[test]
+fn bad_placements_are_reported() {
+    // a 100 x 100 square and a ring with nothing in the middle
+    let square = GrayImage::from_pixel(100, 100, Luma([255]));
+    let ring = GrayImage::from_fn(100, 100, |x, y| {
+        let edge = x < 10 || y < 10 || x >= 90 || y >= 90;
+        Luma([if edge { 255 } else { 0 }])
+    });
+    let small = GrayImage::from_pixel(20, 20, Luma([255]));
+    let glyphs = [
+        (Origin { x: 200, y: 100 }, &square),
+        (Origin { x: 250, y: 150 }, &square),
+        (Origin { x: -10, y: 100 }, &square),
+        (Origin { x: 1850, y: 700 }, &square),
+        (Origin { x: 50, y: 400 }, &square),
+        // inside the ring's box, but not on its ink
+        (Origin { x: 600, y: 400 }, &ring),
+        (Origin { x: 640, y: 440 }, &small),
+        // in the margin above and below
+        (Origin { x: 900, y: 20 }, &small),
+        (Origin { x: 1200, y: 780 }, &square),
+    ];
+    assert_eq!(
+        layout::validate(&glyphs, SCREEN_WIDTH, SCREEN_HEIGHT),
+        [
+            Problem::OffScreen {
+                index: 2,
+                origin: glyphs[2].0
+            },
+            Problem::OffScreen {
+                index: 3,
+                origin: glyphs[3].0
+            },
+            Problem::InMargin {
+                index: 4,
+                origin: glyphs[4].0
+            },
+            Problem::InMargin {
+                index: 7,
+                origin: glyphs[7].0
+            },
+            Problem::InMargin {
+                index: 8,
+                origin: glyphs[8].0
+            },
+            Problem::Overlap {
+                first: 0,
+                second: 1
+            },
+        ]
+    );
+}
+
+/// `placements` as read off the screen: lines top to bottom, every line left to right.
+fn lines(placements: &[Placement], face: &Face) -> Vec<String> {
+    let baseline = |p: &Placement| p.origin.y + face.metrics(p.character).bearing_y;
+    let mut sorted = placements.to_vec();
+    sorted.sort_by_key(|p| (baseline(p), p.origin.x));
+    let mut lines: Vec<String> = vec![];
+    for (i, p) in sorted.iter().enumerate() {
+        if i == 0 || baseline(p) != baseline(&sorted[i - 1]) {
+            lines.push(String::new());
+        }
+        lines.last_mut().unwrap().push(p.character);
+    }
+    lines
+}
+
+#[test]
+fn strategies_place_as_specified() {
+    let face = Face::new();
+    let layout = |message, strategy, seed| {
+        let options = Options {
+            strategy,
+            seed,
+            jitter: 0,
+        };
+        layout::layout(message, SCREEN_WIDTH, SCREEN_HEIGHT, &face, &options).unwrap()
+    };
+    let long = MESSAGES[2];
+
+    // one line, in order, its ink centered
+    let single = layout("hxp{W4lly}", Strategy::SingleLine, 0);
+    assert_eq!(lines(&single, &face), ["hxp{W4lly}"]);
+    let left = single[0].origin.x;
+    let last = single.last().unwrap();
+    let right = last.origin.x + face.metrics(last.character).width as i16;
+    assert!((left - (SCREEN_WIDTH as i16 - right)).abs() <= 1);
+    assert!(layout::layout(
+        long,
+        SCREEN_WIDTH,
+        SCREEN_HEIGHT,
+        &face,
+        &Options {
+            strategy: Strategy::SingleLine,
+            seed: 0,
+            jitter: 0
+        }
+    )
+    .is_err());
+
+    // several lines, in reading order, broken after underscores
+    let wrapped = layout(long, Strategy::Wrapped, 0);
+    let read: Vec<char> = wrapped.iter().map(|p| p.character).collect();
+    assert_eq!(read, long.chars().collect::<Vec<_>>());
+    let wrapped_lines = lines(&wrapped, &face);
+    assert!(wrapped_lines.len() > 1);
+    assert_eq!(wrapped_lines.concat(), long);
+    for line in &wrapped_lines[..wrapped_lines.len() - 1] {
+        assert!(line.ends_with('_'), "{:?}", wrapped_lines);
+    }
+    // spaces break lines too, and aren't drawn
+    let spaced = lines(&layout(MESSAGES[3], Strategy::Wrapped, 0), &face);
+    assert_eq!(spaced.concat(), MESSAGES[3].replace(' ', ""));
+
+    // still reads as the message, but drawn in another order
+    for seed in 0..5 {
+        let scrambled = layout(long, Strategy::Scrambled, seed);
+        assert_eq!(lines(&scrambled, &face).concat(), long);
+        let drawn: Vec<char> = scrambled.iter().map(|p| p.character).collect();
+        assert_ne!(drawn, read);
+    }
+}
+
+#[test]
+fn seeds_decide_the_layout() {
+    let face = Face::new();
+    let layout = |seed| {
+        let options = Options {
+            strategy: Strategy::Scrambled,
+            seed,
+            jitter: 200,
+        };
+        layout::layout(MESSAGES[0], SCREEN_WIDTH, SCREEN_HEIGHT, &face, &options).unwrap()
+    };
+    assert_eq!(layout(1), layout(1));
+    assert_ne!(layout(1), layout(2));
+}
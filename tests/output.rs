@@ -0,0 +1,108 @@
+// Which monitors `--output` picks, and with `--output all` which of them shows which glyph.
+use harlem_shake::font::Face;
+use harlem_shake::layout::{self, Options, Strategy, Typeface, SCREEN_HEIGHT, SCREEN_WIDTH};
+use harlem_shake::output::{self, Monitor, NoOutput, Target};
+
+fn monitor(name: &str, x: i16, y: i16, width: u16, height: u16, primary: bool) -> Monitor {
+    Monitor {
+        name: name.to_string(),
+        x,
+        y,
+        width,
+        height,
+        primary,
+    }
+}
+
+/// Two 1080p monitors side by side, the right one primary.
+fn side_by_side() -> Vec<Monitor> {
+    vec![
+        monitor("DP-1", 0, 0, 1920, 1080, false),
+        monitor("HDMI-1", 1920, 0, 1920, 1080, true),
+    ]
+}
+
+#[test]
+fn targets_are_selected() {
+    let monitors = side_by_side();
+    let select = |target| output::select(monitors.clone(), &target);
+    assert_eq!(select(Target::Primary), Ok(vec![monitors[1].clone()]));
+    assert_eq!(
+        select(Target::Output("DP-1".to_string())),
+        Ok(vec![monitors[0].clone()])
+    );
+    assert_eq!(select(Target::All), Ok(monitors.clone()));
+    assert_eq!(
+        select(Target::Output("VGA-1".to_string())),
+        Err(NoOutput {
+            name: "VGA-1".to_string(),
+            available: vec!["DP-1".to_string(), "HDMI-1".to_string()],
+        })
+    );
+
+    // without a primary output, the leftmost one
+    let mut unmarked = monitors.clone();
+    unmarked[1].primary = false;
+    assert_eq!(
+        output::select(unmarked, &Target::Primary),
+        Ok(vec![monitors[0].clone()])
+    );
+}
+
+#[test]
+fn all_monitors_read_as_one() {
+    let monitors = side_by_side();
+    let area = output::area(&monitors);
+    assert_eq!(
+        (area.x, area.y, area.width, area.height),
+        (0, 0, 3840, 1080)
+    );
+
+    let face = Face::new();
+    let options = Options {
+        strategy: Strategy::SingleLine,
+        seed: 0,
+        jitter: 0,
+    };
+    let message = "hxp{W4lly}";
+    let placements = layout::layout(message, SCREEN_WIDTH, SCREEN_HEIGHT, &face, &options).unwrap();
+    let transform = area.transform();
+    let mut shown = vec![];
+    for placement in &placements {
+        // like put_char
+        let origin = transform.origin(placement.origin);
+        let metrics = face.metrics(placement.character);
+        let (width, height) = transform.size(metrics.width as u32, metrics.height as u32);
+        let (x, y) = (
+            origin.x as i32 + width as i32 / 2,
+            origin.y as i32 + height as i32 / 2,
+        );
+        let monitor = output::shown_on(&monitors, x, y);
+        assert!(monitor.clip().x <= x && x < monitor.clip().x + monitor.clip().width as i32);
+        shown.push((origin.x, monitor.name.as_str()));
+    }
+
+    // left to right, the message runs off the first monitor onto the second, not back and forth
+    shown.sort();
+    let names: Vec<_> = shown.iter().map(|(_, name)| *name).collect();
+    let split = names.iter().position(|&name| name == "HDMI-1").unwrap();
+    assert!(split > 0, "{:?}", names);
+    assert!(
+        names[split..].iter().all(|&name| name == "HDMI-1"),
+        "{:?}",
+        names
+    );
+}
+
+#[test]
+fn gaps_go_to_the_closest_monitor() {
+    let monitors = vec![
+        monitor("left", 0, 0, 100, 100, true),
+        monitor("right", 200, 50, 100, 100, false),
+    ];
+    assert_eq!(output::shown_on(&monitors, 140, 60).name, "left");
+    assert_eq!(output::shown_on(&monitors, 160, 60).name, "right");
+    // below the left one, but closer to the right one
+    assert_eq!(output::shown_on(&monitors, 190, 140).name, "right");
+    assert_eq!(output::shown_on(&monitors, 50, 50).name, "left");
+}
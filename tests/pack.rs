@@ -0,0 +1,160 @@
+// A pack sealed with the build's key loads from a file or stdin, anything else is refused.
+use harlem_shake::atlas::{HEADER_LEN, VERSION};
+use harlem_shake::crypto::{self, NONCE_LEN};
+use harlem_shake::font::Face;
+use harlem_shake::layout::{self, Options, Strategy, SCREEN_HEIGHT, SCREEN_WIDTH};
+use harlem_shake::manifest::ENTRY_LEN;
+use harlem_shake::pack;
+use std::fs;
+use std::path::PathBuf;
+
+#[path = "common/render.rs"]
+mod render;
+use render::render;
+
+static KEY: &[u8; 32] = include_bytes!(concat!(env!("OUT_DIR"), "/key.bin"));
+
+const MESSAGE: &str = "packed{and_shipped}";
+
+/// `MESSAGE` laid out like `--text` lays it out, sealed with `key`.
+fn sealed(key: &[u8; 32]) -> Vec<u8> {
+    seal(key, MESSAGE).to_pack()
+}
+
+fn seal(key: &[u8; 32], message: &str) -> pack::Sealed {
+    let face = Face::new();
+    let options = Options {
+        strategy: Strategy::Wrapped,
+        seed: 0,
+        jitter: 0,
+    };
+    let placements = layout::layout(message, SCREEN_WIDTH, SCREEN_HEIGHT, &face, &options).unwrap();
+    pack::seal(key, &placements, &face)
+}
+
+#[test]
+fn packs_are_drawn() {
+    let tmp = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let pack = tmp.join("message.pack");
+    fs::write(&pack, sealed(KEY)).unwrap();
+
+    let text = tmp.join("pack-text.pbm");
+    assert_eq!(render(&text, &["--text", MESSAGE], b""), Some(0));
+    let from_file = tmp.join("pack-file.pbm");
+    assert_eq!(
+        render(&from_file, &["--pack", pack.to_str().unwrap()], b""),
+        Some(0)
+    );
+    let from_stdin = tmp.join("pack-stdin.pbm");
+    assert_eq!(render(&from_stdin, &["--pack", "-"], &sealed(KEY)), Some(0));
+
+    let text = fs::read(text).unwrap();
+    assert_eq!(fs::read(from_file).unwrap(), text);
+    assert_eq!(fs::read(from_stdin).unwrap(), text);
+}
+
+/// `pack` with its atlas changed by `edit`, and the manifest sealed again to match, so the atlas
+/// is what gets refused.
+fn reatlas(pack: &[u8], edit: impl FnOnce(&mut [u8])) -> Vec<u8> {
+    let (atlas, manifest) = pack::split(pack).unwrap();
+    let key = crypto::manifest_key(KEY);
+    let manifest = crypto::open(&key, &pack::manifest_aad(atlas), manifest).unwrap();
+    let mut atlas = atlas.to_vec();
+    edit(&mut atlas);
+    let manifest = crypto::seal(&key, &pack::manifest_aad(&atlas), &manifest);
+    atlas.extend(manifest);
+    atlas
+}
+
+#[test]
+fn bad_packs_are_refused() {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("pack-refused.pbm");
+    let pack = sealed(KEY);
+
+    // another build's key
+    assert_eq!(render(&path, &["--pack", "-"], &sealed(&[0; 32])), Some(9));
+    // the manifest comes last
+    let mut tampered = pack.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    assert_eq!(render(&path, &["--pack", "-"], &tampered), Some(9));
+    // both halves authentic, from two packs with the same key and the same characters
+    let mut mixed = seal(KEY, "shipped{and_packed}").atlas;
+    mixed.extend(seal(KEY, MESSAGE).manifest);
+    assert_eq!(render(&path, &["--pack", "-"], &mixed), Some(9));
+    // every way the atlas can be refused has its own code
+    let index_len = u32::from_le_bytes(pack[12..16].try_into().unwrap()) as usize;
+    let glyph = reatlas(&pack, |atlas| atlas[HEADER_LEN + index_len] ^= 1);
+    assert_eq!(render(&path, &["--pack", "-"], &glyph), Some(10));
+    assert_eq!(render(&path, &["--pack", "-"], &pack[..100]), Some(19));
+    let mut newer = pack.clone();
+    newer[4..6].copy_from_slice(&(VERSION + 1).to_le_bytes());
+    assert_eq!(render(&path, &["--pack", "-"], &newer), Some(20));
+    let index = reatlas(&pack, |atlas| atlas[HEADER_LEN] ^= 1);
+    assert_eq!(render(&path, &["--pack", "-"], &index), Some(21));
+    assert_eq!(render(&path, &["--pack", "/nonexistent"], b""), Some(17));
+    assert_eq!(
+        render(&path, &["--pack", "-", "--text", MESSAGE], &pack),
+        Some(2)
+    );
+}
+
+/// `pack` with its manifest changed by `edit` and sealed again with the manifest key, like
+/// someone who lifted that key alone could.
+fn reseal(pack: &[u8], edit: impl FnOnce(&mut [u8])) -> Vec<u8> {
+    let (atlas, manifest) = pack::split(pack).unwrap();
+    let key = crypto::manifest_key(KEY);
+    let aad = pack::manifest_aad(atlas);
+    let mut manifest = crypto::open(&key, &aad, manifest).unwrap();
+    edit(&mut manifest);
+    let mut resealed = atlas.to_vec();
+    resealed.extend(crypto::seal(&key, &aad, &manifest));
+    resealed
+}
+
+#[test]
+fn glyphs_stay_where_they_were_placed() {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("pack-moved.pbm");
+    let pack = sealed(KEY);
+    // resealing an unchanged manifest changes nothing
+    assert_eq!(
+        render(&path, &["--pack", "-"], &reseal(&pack, |_| ())),
+        Some(0)
+    );
+
+    // one pixel to the right
+    let moved = reseal(&pack, |manifest| {
+        let x = i16::from_le_bytes([manifest[ENTRY_LEN], manifest[ENTRY_LEN + 1]]);
+        manifest[ENTRY_LEN..ENTRY_LEN + 2].copy_from_slice(&(x + 1).to_le_bytes());
+    });
+    assert_eq!(render(&path, &["--pack", "-"], &moved), Some(18));
+    // two glyphs drawn in each other's turn
+    let reordered = reseal(&pack, |manifest| {
+        let (a, b) = manifest.split_at_mut(ENTRY_LEN);
+        a.swap_with_slice(&mut b[..ENTRY_LEN]);
+    });
+    assert_eq!(render(&path, &["--pack", "-"], &reordered), Some(18));
+    // a glyph's ticket given to another
+    let swapped = reseal(&pack, |manifest| {
+        let (a, b) = manifest.split_at_mut(ENTRY_LEN);
+        a[4..].swap_with_slice(&mut b[4..ENTRY_LEN]);
+    });
+    assert_eq!(render(&path, &["--pack", "-"], &swapped), Some(18));
+}
+
+#[test]
+fn packs_never_share_a_nonce() {
+    // what builds with the same HARLEM_SHAKE_SEED and other messages write
+    let messages = [MESSAGE, "packed{and_shipped!}", "other", "x"];
+    let packs: Vec<_> = messages.iter().map(|m| seal(KEY, m)).collect();
+    let mut nonces: Vec<&[u8]> = vec![];
+    for pack in &packs {
+        nonces.push(&pack.atlas[HEADER_LEN..HEADER_LEN + NONCE_LEN]);
+        nonces.push(&pack.manifest[..NONCE_LEN]);
+    }
+    for (i, nonce) in nonces.iter().enumerate() {
+        assert!(!nonces[i + 1..].contains(nonce), "nonce {} is reused", i);
+    }
+
+    // and the same message still comes out the same
+    assert_eq!(seal(KEY, MESSAGE).to_pack(), packs[0].to_pack());
+}
@@ -0,0 +1,28 @@
+// Every build has to stay solvable: the solver run on the binary under test must come up with the
+// same picture the binary itself renders.
+use std::path::PathBuf;
+use std::process::Command;
+
+#[path = "common/render.rs"]
+mod render;
+use render::render;
+
+#[test]
+fn solver_recovers_rendered_flag() {
+    let tmp = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let solved = tmp.join("solved.png");
+    let rendered = tmp.join("rendered.png");
+
+    let status = Command::new(env!("CARGO_BIN_EXE_solve"))
+        .arg(env!("CARGO_BIN_EXE_harlem_shake"))
+        .arg(&solved)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    assert_eq!(render(&rendered, &[], b""), Some(0));
+
+    let solved = image::open(solved).unwrap().into_luma8();
+    let rendered = image::open(rendered).unwrap().into_luma8();
+    assert!(solved == rendered);
+}
@@ -0,0 +1,135 @@
+// Dilation and erosion of the mask, checked against doing it the slow way, pixel by pixel, and
+// what it does at its limits.
+use harlem_shake::stroke::{Stroke, MAX_RADIUS};
+use image::{GrayImage, Luma};
+use std::path::PathBuf;
+
+#[path = "common/render.rs"]
+mod render;
+use render::render;
+
+/// A plus sign with a gray edge and a lone pixel, so both thresholds and corners matter.
+fn glyph() -> GrayImage {
+    GrayImage::from_fn(20, 30, |x, y| {
+        let luma = match (x, y) {
+            (8..=11, 5..=24) | (3..=16, 13..=16) => 255,
+            (7, 5..=24) => 100,
+            (18, 2) => 255,
+            _ => 0,
+        };
+        Luma([luma])
+    })
+}
+
+fn set(image: &GrayImage, x: i64, y: i64) -> bool {
+    x >= 0
+        && y >= 0
+        && x < image.width() as i64
+        && y < image.height() as i64
+        && image.get_pixel(x as u32, y as u32).0[0] >= 96
+}
+
+fn expected(glyph: &GrayImage, radius: i64) -> GrayImage {
+    let r = radius.abs();
+    let border = radius.max(0);
+    let (width, height) = (
+        glyph.width() + 2 * border as u32,
+        glyph.height() + 2 * border as u32,
+    );
+    GrayImage::from_fn(width, height, |x, y| {
+        let (x, y) = (x as i64 - border, y as i64 - border);
+        let mut window = (-r..=r).flat_map(|dy| (-r..=r).map(move |dx| (dx, dy)));
+        let on = match radius > 0 {
+            true => window.any(|(dx, dy)| set(glyph, x + dx, y + dy)),
+            false => window.all(|(dx, dy)| set(glyph, x + dx, y + dy)),
+        };
+        Luma([if on { 255 } else { 0 }])
+    })
+}
+
+#[test]
+fn matches_brute_force() {
+    let glyph = glyph();
+    for radius in -3..=3 {
+        let stroke = Stroke {
+            threshold: 96,
+            radius,
+        };
+        let (image, border) = stroke.apply(&glyph, 1.0);
+        assert_eq!(border, radius.max(0) as u32);
+        assert!(
+            image == expected(&glyph, radius as i64),
+            "radius {}",
+            radius
+        );
+    }
+}
+
+#[test]
+fn radius_scales_with_the_glyph() {
+    let stroke = Stroke {
+        threshold: 96,
+        radius: 2,
+    };
+    assert_eq!(stroke.apply(&glyph(), 2.0).1, 4);
+    assert_eq!(stroke.apply(&glyph(), 0.5).1, 1);
+}
+
+#[test]
+fn threshold_picks_gray_pixels() {
+    let glyph = glyph();
+    let count = |threshold| {
+        let stroke = Stroke {
+            threshold,
+            radius: 0,
+        };
+        let (image, _) = stroke.apply(&glyph, 1.0);
+        image.pixels().filter(|p| p.0[0] == 255).count()
+    };
+    // the gray column, minus where the crossbar covers it
+    assert_eq!(count(100) - count(101), 16);
+}
+
+#[test]
+fn empty_glyphs_stay_empty() {
+    for (width, height) in [(0, 0), (0, 5), (5, 0)] {
+        for radius in [-3, 3] {
+            let stroke = Stroke {
+                threshold: 96,
+                radius,
+            };
+            let (image, border) = stroke.apply(&GrayImage::new(width, height), 1.0);
+            assert_eq!((image.dimensions(), border), ((width, height), 0));
+        }
+    }
+}
+
+#[test]
+fn radius_is_bounded() {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("stroke-bounded.pbm");
+    let render = |radius: i32| render(&path, &["--stroke", &radius.to_string()], b"");
+    let max = MAX_RADIUS as i32;
+    // glyphs grown into each other are refused, however far the radius is allowed
+    assert_eq!(render(max), Some(23));
+    assert_eq!(render(-max), Some(0));
+    assert_eq!(render(max + 1), Some(2));
+    assert_eq!(render(-max - 1), Some(2));
+    // would overflow the origins, and take ages
+    assert_eq!(render(3000), Some(2));
+}
+
+#[test]
+fn stroked_glyphs_are_validated() {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("stroke-validated.pbm");
+    let render = |radius: i32| {
+        let radius = radius.to_string();
+        render(
+            &path,
+            &["--stroke", &radius, "--text", "stroked{and_fat}"],
+            b"",
+        )
+    };
+    // the closest two glyphs, "r" and "o", grow into each other at 4
+    assert_eq!(render(3), Some(0));
+    assert_eq!(render(4), Some(23));
+}
@@ -0,0 +1,60 @@
+// `--text` draws whatever the embedded font covers, at any `--size`, and says so when it doesn't.
+use std::path::{Path, PathBuf};
+
+#[path = "common/render.rs"]
+mod render;
+
+fn render(text: &str, path: &Path) -> Option<i32> {
+    render_with(text, path, &[])
+}
+
+fn render_with(text: &str, path: &Path, args: &[&str]) -> Option<i32> {
+    let args = [&["--text", text], args].concat();
+    render::render(path, &args, b"")
+}
+
+#[test]
+fn text_is_rendered() {
+    let tmp = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let path = tmp.join("text.png");
+    assert_eq!(render("Grüße, wörld! €5 → ☺", &path), Some(0));
+    let mask = image::open(&path).unwrap().into_luma8();
+    assert!(mask.pixels().any(|p| p.0[0] != 0));
+
+    // the same string drawn with a different character
+    let other = tmp.join("text-other.png");
+    assert_eq!(render("Grüße, wörld! €6 → ☺", &other), Some(0));
+    assert!(mask != image::open(&other).unwrap().into_luma8());
+}
+
+#[test]
+fn missing_glyphs_and_overlong_text_are_refused() {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("text-refused.png");
+    assert_eq!(render("漢字", &path), Some(16));
+    assert_eq!(render(&"x".repeat(1000), &path), Some(15));
+}
+
+#[test]
+fn size_scales_the_text() {
+    let tmp = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let ink = |size: &str| {
+        let path = tmp.join(format!("text-size-{}.png", size));
+        assert_eq!(render_with("hxp{sized}", &path, &["--size", size]), Some(0));
+        let mask = image::open(&path).unwrap().into_luma8();
+        mask.pixels().filter(|p| p.0[0] != 0).count()
+    };
+    let (small, default, large) = (ink("40"), ink("130"), ink("300"));
+    assert!(small < default && default < large);
+
+    // too long for the default size, fits at a smaller one
+    let path = tmp.join("text-size-long.png");
+    let long = "x".repeat(1000);
+    assert_eq!(render(&long, &path), Some(15));
+    assert_eq!(render_with(&long, &path, &["--size", "20"]), Some(0));
+
+    for size in ["0", "9", "501", "huge", "NaN"] {
+        assert_eq!(render_with("x", &path, &["--size", size]), Some(2));
+    }
+    // baked in glyphs have their size already
+    assert_eq!(render::render(&path, &["--size", "40"], b""), Some(2));
+}
@@ -0,0 +1,262 @@
+// The intended alternative solve: sniff the X protocol like xscope would and read the flag out of
+// the input shape. Runs the overlay on Xvfb through a recording proxy, rebuilds the bitmap from
+// the recorded requests and checks it against `harlem_shake render`. Needs Xvfb, so it only runs
+// with `cargo test -- --ignored`.
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use x11rb::connection::{Connection, RequestConnection};
+use x11rb::protocol::xfixes;
+use x11rb::protocol::xproto::ImageOrder;
+
+#[path = "common/render.rs"]
+mod render;
+#[path = "common/xvfb.rs"]
+mod xvfb;
+use render::render;
+use xvfb::{Process, Xvfb};
+
+const WIDTH: u16 = 1900;
+const HEIGHT: u16 = 900;
+
+// opcodes, see the X11 and XFixes protocol specs
+const CREATE_PIXMAP: u8 = 53;
+const PUT_IMAGE: u8 = 72;
+const XFIXES_CREATE_REGION_FROM_BITMAP: u8 = 6;
+const XFIXES_SET_WINDOW_SHAPE_REGION: u8 = 21;
+const SHAPE_KIND_INPUT: u8 = 2;
+
+/// Forward one client at `path` to the X server at `server`, recording what the client sends.
+fn proxy(path: &Path, server: String) -> Arc<Mutex<Vec<u8>>> {
+    let listener = UnixListener::bind(path).unwrap();
+    let recorded = Arc::new(Mutex::new(vec![]));
+    let sink = recorded.clone();
+    thread::spawn(move || {
+        let (mut client, _) = listener.accept().unwrap();
+        let mut server = UnixStream::connect(server).unwrap();
+
+        let (mut from_server, mut to_client) =
+            (server.try_clone().unwrap(), client.try_clone().unwrap());
+        thread::spawn(move || std::io::copy(&mut from_server, &mut to_client));
+
+        let mut buf = [0; 65536];
+        while let Ok(n @ 1..) = client.read(&mut buf) {
+            sink.lock().unwrap().extend_from_slice(&buf[..n]);
+            if server.write_all(&buf[..n]).is_err() {
+                break;
+            }
+        }
+    });
+    recorded
+}
+
+/// The requests in a recorded client stream, as (major opcode, whole request). Big requests come
+/// without their extra length field, so all fields are at their usual offsets. Assumes a little
+/// endian client.
+fn requests(stream: &[u8]) -> Vec<(u8, Vec<u8>)> {
+    let pad = |n: usize| n.div_ceil(4) * 4;
+    let u16_at = |data: &[u8], at: usize| u16::from_le_bytes([data[at], data[at + 1]]) as usize;
+
+    if stream.len() < 12 {
+        return vec![];
+    }
+    assert_eq!(stream[0], b'l', "big endian client");
+    let mut at = 12 + pad(u16_at(stream, 6)) + pad(u16_at(stream, 8));
+
+    let mut requests = vec![];
+    while at + 4 <= stream.len() {
+        let len = u16_at(stream, at + 2) * 4;
+        if len > 0 {
+            if at + len > stream.len() {
+                break;
+            }
+            requests.push((stream[at], stream[at..at + len].to_vec()));
+            at += len;
+            continue;
+        }
+
+        // BIG-REQUESTS
+        if at + 8 > stream.len() {
+            break;
+        }
+        let len = u32_at(stream, at + 4) as usize * 4;
+        if at + len > stream.len() {
+            break;
+        }
+        let mut request = stream[at..at + 4].to_vec();
+        request.extend_from_slice(&stream[at + 8..at + len]);
+        requests.push((stream[at], request));
+        at += len;
+    }
+    requests
+}
+
+fn u32_at(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+}
+
+/// Rebuild the bitmap of the last input shape set in `stream`. `None` until there is one.
+fn input_shape(
+    stream: &[u8],
+    xfixes_opcode: u8,
+    bit_order: ImageOrder,
+    scanline_pad: usize,
+) -> Option<Vec<Vec<bool>>> {
+    let requests = requests(stream);
+    let is_xfixes = |request: &Vec<u8>, minor| request[0] == xfixes_opcode && request[1] == minor;
+
+    // SetWindowShapeRegion(window, INPUT, x, y, region)
+    let region = requests
+        .iter()
+        .rev()
+        .map(|(_, r)| r)
+        .find(|r| is_xfixes(r, XFIXES_SET_WINDOW_SHAPE_REGION) && r[8] == SHAPE_KIND_INPUT)
+        .map(|r| u32_at(r, 16))?;
+
+    // CreateRegionFromBitmap(region, bitmap)
+    let created = requests
+        .iter()
+        .rposition(|(_, r)| {
+            is_xfixes(r, XFIXES_CREATE_REGION_FROM_BITMAP) && u32_at(r, 4) == region
+        })
+        .unwrap();
+    let bitmap = u32_at(&requests[created].1, 8);
+
+    let mut pixels = vec![vec![false; WIDTH as usize]; HEIGHT as usize];
+    for (major, request) in &requests[..created] {
+        match *major {
+            // a fresh pixmap with a reused id
+            CREATE_PIXMAP if u32_at(request, 4) == bitmap => {
+                pixels = vec![vec![false; WIDTH as usize]; HEIGHT as usize];
+            }
+            PUT_IMAGE if u32_at(request, 4) == bitmap => {
+                let width = u16::from_le_bytes([request[12], request[13]]) as usize;
+                let height = u16::from_le_bytes([request[14], request[15]]) as usize;
+                let dst_x = i16::from_le_bytes([request[16], request[17]]) as usize;
+                let dst_y = i16::from_le_bytes([request[18], request[19]]) as usize;
+                let left_pad = request[20] as usize;
+                let data = &request[24..];
+
+                let stride = (width + left_pad).div_ceil(scanline_pad) * scanline_pad / 8;
+                for y in 0..height {
+                    for x in 0..width {
+                        let bit = x + left_pad;
+                        let byte = data[y * stride + bit / 8];
+                        let shift = match bit_order {
+                            ImageOrder::LSB_FIRST => bit % 8,
+                            _ => 7 - bit % 8,
+                        };
+                        pixels[dst_y + y][dst_x + x] = byte >> shift & 1 == 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Some(pixels)
+}
+
+/// Run the overlay with `args` behind the proxy, and check the input shape it sets against
+/// `harlem_shake render`.
+fn sniff(args: &[&str]) {
+    // one at a time, or two servers could pick the same display
+    static XVFB: Mutex<()> = Mutex::new(());
+    let _xvfb = XVFB.lock().unwrap_or_else(|e| e.into_inner());
+    let xvfb = Xvfb::start(WIDTH, HEIGHT).expect("Xvfb not found");
+
+    // the server's opcodes and bitmap format, to decode the recording with
+    let (conn, _) = x11rb::connect(Some(&format!(":{}", xvfb.display))).unwrap();
+    let xfixes_opcode = conn
+        .extension_information(xfixes::X11_EXTENSION_NAME)
+        .unwrap()
+        .expect("Xvfb without XFixes")
+        .major_opcode;
+    // the mask goes out as a depth 1 ZPixmap
+    let bit_order = conn.setup().bitmap_format_bit_order;
+    let scanline_pad = conn
+        .setup()
+        .pixmap_formats
+        .iter()
+        .find(|format| format.depth == 1)
+        .expect("no depth 1 pixmap format")
+        .scanline_pad as usize;
+
+    let tmp = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let socket = tmp.join(format!("xproxy-{}", xvfb.display));
+    let _ = std::fs::remove_file(&socket);
+    let recorded = proxy(&socket, xvfb.socket());
+
+    let mut overlay = Command::new(env!("CARGO_BIN_EXE_harlem_shake"))
+        // a path is taken as the socket to connect to
+        .env("DISPLAY", &socket)
+        .args(args)
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let output = Arc::new(Mutex::new(vec![]));
+    let lines = BufReader::new(overlay.stdout.take().unwrap()).lines();
+    let sink = output.clone();
+    thread::spawn(move || {
+        lines
+            .map_while(Result::ok)
+            .for_each(|line| sink.lock().unwrap().push(line))
+    });
+    let _overlay = Process(overlay);
+
+    let start = Instant::now();
+    let sniffed = loop {
+        let stream = recorded.lock().unwrap().clone();
+        if let Some(pixels) = input_shape(&stream, xfixes_opcode, bit_order, scanline_pad) {
+            break pixels;
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(30),
+            "no input shape set"
+        );
+        thread::sleep(Duration::from_millis(100));
+    };
+    // the proxy doesn't pass fds on, so MIT-SHM can't work through it
+    let uploads = "Uploading images over the socket".to_string();
+    while !output.lock().unwrap().contains(&uploads) {
+        assert!(
+            start.elapsed() < Duration::from_secs(30),
+            "{:?}",
+            output.lock().unwrap()
+        );
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    let rendered = tmp.join("xproxy-rendered.png");
+    assert_eq!(render(&rendered, &[], b""), Some(0));
+    let rendered = image::open(rendered).unwrap().into_luma8();
+
+    assert!(
+        sniffed.iter().flatten().any(|&set| set),
+        "empty input shape"
+    );
+    for (x, y, pixel) in rendered.enumerate_pixels() {
+        assert_eq!(
+            sniffed[y as usize][x as usize],
+            pixel.0[0] != 0,
+            "pixel {}, {}",
+            x,
+            y
+        );
+    }
+}
+
+#[test]
+#[ignore = "needs Xvfb, run with `cargo test -- --ignored`"]
+fn input_shape_leaks_the_flag() {
+    sniff(&[]);
+}
+
+#[test]
+#[ignore = "needs Xvfb, run with `cargo test -- --ignored`"]
+fn input_shape_leaks_the_flag_without_shm() {
+    sniff(&["--no-shm"]);
+}